@@ -1,12 +1,13 @@
 use crate::bewerb::Bewerb;
 use crate::container::{HasId, UidContainer};
 use crate::group::{Group, GroupId};
+use crate::tableau::{Bout, BoutId};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
 use crate::tournament::{ArenaData, ArenaIdentifier};
 
-#[derive(Clone, Debug, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Default, Deserialize, Serialize)]
 pub struct ArenaSlotId {
     pub day_id: u32,
     pub timeslot_id: u32,
@@ -33,29 +34,102 @@ impl From<ArenaIdentifier> for ArenaSlotId {
     }
 }
 
+/// Why an arena slot can not take any group or only some.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum SlotBlock {
+    /// no group at all, e.g. maintenance, warm-up or a ceremony
+    Blocked { reason: String },
+    /// only groups of the bewerb, and of the round if given
+    Reserved {
+        bewerb_id: u32,
+        round_id: Option<u32>,
+        reason: String,
+    },
+}
+
+impl SlotBlock {
+    pub fn allows(&self, group: &GroupId) -> bool {
+        match self {
+            Self::Blocked { .. } => false,
+            Self::Reserved {
+                bewerb_id,
+                round_id,
+                ..
+            } => *bewerb_id == group.bewerb_id && round_id.is_none_or(|x| x == group.round_id),
+        }
+    }
+
+    /// Elimination bouts have no round, so only a reservation for the
+    /// whole bewerb allows them.
+    pub fn allows_bout(&self, bout: &BoutId) -> bool {
+        match self {
+            Self::Blocked { .. } => false,
+            Self::Reserved {
+                bewerb_id,
+                round_id,
+                ..
+            } => *bewerb_id == bout.bewerb_id && round_id.is_none(),
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        match self {
+            Self::Blocked { reason } | Self::Reserved { reason, .. } => reason,
+        }
+    }
+}
+
+/// What an arena slot is used for.
+#[derive(Clone, Debug)]
+pub enum Occupant {
+    Group(Arc<Group>),
+    Bout(Arc<Bout>),
+}
+
+impl Occupant {
+    pub fn bewerb_id(&self) -> u32 {
+        match self {
+            Self::Group(x) => x.id().bewerb_id,
+            Self::Bout(x) => x.id().bewerb_id,
+        }
+    }
+
+    pub fn bewerb_name(&self) -> String {
+        match self {
+            Self::Group(x) => x.id().bewerb_name,
+            Self::Bout(x) => x.id().bewerb_name,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct ArenaSlot {
     pub id: ArenaSlotId,
-    group: Mutex<Option<Arc<Group>>>,
+    pub piste: u32,
+    occupant: Mutex<Option<Occupant>>,
+    block: Mutex<Option<SlotBlock>>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct ArenaSlotSaveable {
     id: ArenaSlotId,
+    #[serde(default)]
+    piste: Option<u32>,
     group: Option<GroupId>,
+    #[serde(default)]
+    bout: Option<BoutId>,
+    #[serde(default)]
+    block: Option<SlotBlock>,
 }
 
 impl From<&ArenaSlot> for ArenaSlotSaveable {
     fn from(arena_slot: &ArenaSlot) -> Self {
         Self {
             id: arena_slot.id.clone(),
-            group: arena_slot
-                .group
-                .lock()
-                .unwrap()
-                .as_ref()
-                .cloned()
-                .map(|x| x.id().clone()),
+            piste: Some(arena_slot.piste),
+            group: arena_slot.get_group().map(|x| x.id()),
+            bout: arena_slot.get_bout().map(|x| x.id()),
+            block: arena_slot.get_block(),
         }
     }
 }
@@ -76,21 +150,39 @@ impl ArenaSlot {
             }
         }
 
+        let bout = as_save_able.bout.and_then(|bout_id| {
+            let res = bewerbs
+                .get(bout_id.bewerb_id)
+                .and_then(|x| x.get_bout(&bout_id));
+            if res.is_none() {
+                println!("setup of arena_slot bout \"{:?}\" not fround", bout_id);
+            }
+            res
+        });
+
+        // files written before arena slots had unique ids used the piste as id
+        let piste = as_save_able.piste.unwrap_or(as_save_able.id.arena_slot_id);
+
         let res = Arc::new(Self {
             id: as_save_able.id,
-            group: Mutex::new(None), //TODO
+            piste,
+            occupant: Mutex::new(None), //TODO
+            block: Mutex::new(as_save_able.block),
         });
 
         if let Some(group) = group {
             Group::add_to_arenaslot(group, res.clone());
+        } else if let Some(bout) = bout {
+            Bout::add_to_arenaslot(bout, res.clone());
         }
 
         res
     }
 
-    pub fn new(id: ArenaSlotId) -> Self {
+    pub fn new(id: ArenaSlotId, piste: u32) -> Self {
         Self {
             id,
+            piste,
             ..Default::default()
         }
     }
@@ -99,24 +191,72 @@ impl ArenaSlot {
         &self.id
     }
 
+    pub fn get_occupant(&self) -> Option<Occupant> {
+        self.occupant.lock().unwrap().clone()
+    }
+
+    /// Neither a group nor a bout is put into the slot.
+    pub fn is_free(&self) -> bool {
+        self.occupant.lock().unwrap().is_none()
+    }
+
     pub fn get_group(&self) -> Option<Arc<Group>> {
-        self.group.lock().unwrap().clone()
+        match self.occupant.lock().unwrap().as_ref() {
+            Some(Occupant::Group(x)) => Some(x.clone()),
+            _ => None,
+        }
     }
 
+    /// Puts the group into the slot, `None` frees the slot whatever it
+    /// holds.
     pub fn set_group(&self, id: Option<Arc<Group>>) {
-        *self.group.lock().unwrap() = id;
+        *self.occupant.lock().unwrap() = id.map(Occupant::Group);
+    }
+
+    pub fn get_bout(&self) -> Option<Arc<Bout>> {
+        match self.occupant.lock().unwrap().as_ref() {
+            Some(Occupant::Bout(x)) => Some(x.clone()),
+            _ => None,
+        }
+    }
+
+    /// Puts the bout into the slot, `None` frees the slot whatever it
+    /// holds.
+    pub fn set_bout(&self, bout: Option<Arc<Bout>>) {
+        *self.occupant.lock().unwrap() = bout.map(Occupant::Bout);
+    }
+
+    pub fn get_block(&self) -> Option<SlotBlock> {
+        self.block.lock().unwrap().clone()
+    }
+
+    pub fn set_block(&self, block: Option<SlotBlock>) {
+        *self.block.lock().unwrap() = block;
+    }
+
+    /// Whether the group may be put into the slot regarding its block.
+    pub fn allows(&self, group: &GroupId) -> bool {
+        self.block
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(|x| x.allows(group))
+    }
+
+    /// Whether the bout may be put into the slot regarding its block.
+    pub fn allows_bout(&self, bout: &BoutId) -> bool {
+        self.block
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(|x| x.allows_bout(bout))
     }
 }
 
 impl From<&ArenaSlot> for ArenaData {
     fn from(arena: &ArenaSlot) -> Self {
         let id = Some((&arena.id).into());
-        let group = arena
-            .group
-            .lock()
-            .unwrap()
-            .clone()
-            .map(|x| (&x.id()).into());
+        let group = arena.get_group().map(|x| (&x.id()).into());
 
         Self { id, group }
     }
@@ -126,8 +266,4 @@ impl HasId for ArenaSlot {
     fn get_id(&self) -> u32 {
         self.id.arena_slot_id
     }
-
-    fn set_id(&mut self, id: u32) {
-        self.id.arena_slot_id = id;
-    }
 }
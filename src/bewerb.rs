@@ -1,6 +1,9 @@
-use crate::container::{HasId, UidContainer};
+use crate::container::{HasId, IdAllocator, UidContainer};
+use crate::error::Error;
 use crate::group::{Group, GroupId};
 use crate::round::{Round, RoundSaveable};
+use crate::tableau::{Bout, BoutId, BoutSaveable, Tableau};
+use crate::venue::PisteAttribute;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -30,12 +33,52 @@ impl From<&BewerbId> for BewerbIdentifier {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum Weapon {
+    #[default]
+    Foil,
+    Epee,
+    Sabre,
+}
+
+impl Weapon {
+    /// Average time a pool bout takes including the pauses between bouts.
+    pub fn bout_minutes(&self) -> u32 {
+        match self {
+            Weapon::Foil => 6,
+            Weapon::Epee => 7,
+            Weapon::Sabre => 4,
+        }
+    }
+
+    /// Average time a direct elimination bout takes including the pause
+    /// before the next one.
+    pub fn de_bout_minutes(&self) -> u32 {
+        match self {
+            Weapon::Foil => 15,
+            Weapon::Epee => 15,
+            Weapon::Sabre => 10,
+        }
+    }
+
+    /// Time to fence all bouts of a pool with `n` fencers.
+    pub fn pool_minutes(&self, n: u32) -> u32 {
+        n * n.saturating_sub(1) / 2 * self.bout_minutes()
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Bewerb {
     id: BewerbId,
     pub n_rounds: u32,
     n_groups: u32,
+    pub weapon: Weapon,
     rounds: UidContainer<Round>,
+    /// days the scheduler tries first
+    pub preferred_days: Vec<u32>,
+    /// pistes the bewerb may use, all if empty
+    pub allowed_pistes: Vec<u32>,
+    tableau: Tableau,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -43,7 +86,15 @@ pub struct BewerbSaveable {
     id: BewerbId,
     n_rounds: u32,
     n_groups: u32,
+    #[serde(default)]
+    weapon: Weapon,
     rounds: Vec<RoundSaveable>,
+    #[serde(default)]
+    preferred_days: Vec<u32>,
+    #[serde(default)]
+    allowed_pistes: Vec<u32>,
+    #[serde(default)]
+    tableau: Vec<BoutSaveable>,
 }
 
 impl From<&Bewerb> for BewerbSaveable {
@@ -54,7 +105,11 @@ impl From<&Bewerb> for BewerbSaveable {
             id: bewerb.id.clone(),
             n_rounds: bewerb.n_rounds,
             n_groups: bewerb.n_groups,
+            weapon: bewerb.weapon,
             rounds,
+            preferred_days: bewerb.preferred_days.clone(),
+            allowed_pistes: bewerb.allowed_pistes.clone(),
+            tableau: (&bewerb.tableau).into(),
         }
     }
 }
@@ -70,29 +125,79 @@ impl Bewerb {
             id: bewerb.id.clone(),
             n_rounds: bewerb.n_rounds,
             n_groups: bewerb.n_groups,
+            weapon: bewerb.weapon,
             rounds,
+            preferred_days: bewerb.preferred_days.clone(),
+            allowed_pistes: bewerb.allowed_pistes.clone(),
+            tableau: Tableau::from_saveable(&bewerb.tableau),
         }
     }
 
-    pub fn new(bewerb_id: u32, name: String, n_rounds: u32, n_groups: u32) -> Self {
+    pub fn new(ids: &mut IdAllocator, name: String, n_rounds: u32, n_groups: u32) -> Self {
         let mut res = Bewerb {
             id: BewerbId {
-                bewerb_id,
+                bewerb_id: ids.next(),
                 bewerb_name: name.clone(),
             },
             n_rounds,
             n_groups,
+            weapon: Weapon::default(),
             rounds: UidContainer::default(),
+            preferred_days: Vec::new(),
+            allowed_pistes: Vec::new(),
+            tableau: Tableau::default(),
         };
 
-        for i in 0..n_rounds {
-            let round = Round::new(&res.id, n_groups, i);
-            res.rounds.push(round);
+        for _ in 0..n_rounds {
+            let round = Round::new(ids, &res.id, n_groups);
+            res.rounds.insert(round);
         }
 
         res
     }
 
+    pub fn name(&self) -> &str {
+        &self.id.bewerb_name
+    }
+
+    /// Ids of the rounds in the order they are fenced.
+    pub fn get_round_ids(&self) -> Vec<u32> {
+        self.rounds.iter().map(|x| x.get_id()).collect()
+    }
+
+    pub fn get_groups_of_round(&self, round_id: u32) -> Vec<GroupId> {
+        self.rounds
+            .get(round_id)
+            .map(|x| x.get_all_groups())
+            .unwrap_or_default()
+    }
+
+    pub fn get_required_attributes(&self, round_id: u32) -> Vec<PisteAttribute> {
+        self.rounds
+            .get(round_id)
+            .map(|x| x.required_attributes.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_required_attributes(
+        &mut self,
+        round_id: u32,
+        attributes: Vec<PisteAttribute>,
+    ) -> Result<(), Error> {
+        let Some(round) = self.rounds.get_mut(round_id) else {
+            return Err(Error::InvalidInput(format!(
+                "Ivalid round_id {:?}",
+                round_id
+            )));
+        };
+        round.required_attributes = attributes;
+        Ok(())
+    }
+
+    pub fn allows_piste(&self, piste: u32) -> bool {
+        self.allowed_pistes.is_empty() || self.allowed_pistes.contains(&piste)
+    }
+
     pub fn get_all_groups(&self) -> Vec<GroupId> {
         let mut res = Vec::new();
 
@@ -117,6 +222,22 @@ impl Bewerb {
         let round = self.rounds.get(id.round_id)?;
         round.get_group_by_id(id)
     }
+
+    pub fn id(&self) -> &BewerbId {
+        &self.id
+    }
+
+    pub fn get_tableau(&self) -> &Tableau {
+        &self.tableau
+    }
+
+    pub fn set_tableau(&mut self, tableau: Tableau) {
+        self.tableau = tableau;
+    }
+
+    pub fn get_bout(&self, id: &BoutId) -> Option<Arc<Bout>> {
+        self.tableau.get_bout(id)
+    }
 }
 
 impl From<&Bewerb> for SimpleBewerbData {
@@ -134,7 +255,4 @@ impl HasId for Bewerb {
     fn get_id(&self) -> u32 {
         self.id.bewerb_id
     }
-    fn set_id(&mut self, id: u32) {
-        self.id.bewerb_id = id;
-    }
 }
@@ -0,0 +1,174 @@
+use chrono::NaiveDateTime;
+use std::fmt;
+
+use crate::container::HasId;
+use crate::error::Error;
+use crate::group::GroupId;
+use crate::schedule::Placement;
+use crate::timeslot::TimeslotId;
+use crate::tournament_core::Tournament;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CallStatus {
+    Pending,
+    Called,
+    Recalled,
+}
+
+/// A group to be called to its piste.
+#[derive(Clone, Debug)]
+pub struct Announcement {
+    pub group: GroupId,
+    /// pool number within the round and round number within the bewerb,
+    /// both counted from 1
+    pub pool: u32,
+    pub round: u32,
+    pub start: NaiveDateTime,
+    pub timeslot: TimeslotId,
+    pub piste: u32,
+    pub piste_name: String,
+    /// id and name of each fencer
+    pub fencers: Vec<(u32, String)>,
+    pub referees: Vec<String>,
+    pub status: CallStatus,
+    pub no_shows: Vec<u32>,
+}
+
+impl fmt::Display for Announcement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fencers: Vec<&str> = self.fencers.iter().map(|(_, x)| x.as_str()).collect();
+        write!(
+            f,
+            "Pool {} of {} round {} to piste {}, fencers: {}",
+            self.pool,
+            self.group.bewerb_name,
+            self.round,
+            self.piste_name,
+            fencers.join(", ")
+        )?;
+        if !self.referees.is_empty() {
+            write!(f, ", referees: {}", self.referees.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Tournament {
+    fn get_announcement(&self, placement: &Placement) -> Option<Announcement> {
+        let bewerb = self.bewerbs.get(placement.group.bewerb_id)?;
+        let group = bewerb.get_group_by_id(&placement.group)?;
+        let id = &placement.group;
+
+        let round = bewerb
+            .get_round_ids()
+            .iter()
+            .position(|x| *x == id.round_id)? as u32
+            + 1;
+        let pool = bewerb
+            .get_groups_of_round(id.round_id)
+            .iter()
+            .position(|x| x == id)? as u32
+            + 1;
+
+        let progress = group.get_progress();
+        let status = match progress.calls.len() {
+            0 => CallStatus::Pending,
+            1 => CallStatus::Called,
+            _ => CallStatus::Recalled,
+        };
+
+        Some(Announcement {
+            group: id.clone(),
+            pool,
+            round,
+            start: placement.slot.start,
+            timeslot: TimeslotId {
+                day_id: placement.slot.arena.day_id,
+                timeslot_id: placement.slot.arena.timeslot_id,
+            },
+            piste: placement.slot.piste,
+            piste_name: self.piste_name(placement.slot.piste),
+            fencers: group
+                .get_fencers()
+                .iter()
+                .map(|x| (x.get_id(), x.name()))
+                .collect(),
+            referees: group.get_referees(),
+            status,
+            no_shows: progress.no_shows,
+        })
+    }
+
+    /// Announcements of all scheduled groups that have not started yet,
+    /// ordered by start and piste.
+    pub fn get_call_queue(&self) -> Vec<Announcement> {
+        let mut placements: Vec<Placement> = self
+            .get_placements()
+            .into_iter()
+            .filter(|x| {
+                let progress = self.get_group_progress(&x.group);
+                progress.actual_start.is_none() && progress.actual_end.is_none()
+            })
+            .collect();
+        placements.sort_by_key(|x| (x.slot.start, x.slot.piste));
+
+        placements
+            .iter()
+            .filter_map(|x| self.get_announcement(x))
+            .collect()
+    }
+
+    /// Announcements of the groups starting in the timeslot.
+    pub fn get_timeslot_calls(&self, id: &TimeslotId) -> Vec<Announcement> {
+        self.get_call_queue()
+            .into_iter()
+            .filter(|x| x.timeslot.day_id == id.day_id && x.timeslot.timeslot_id == id.timeslot_id)
+            .collect()
+    }
+
+    pub fn set_group_referees(&mut self, id: &GroupId, referees: Vec<String>) -> Result<(), Error> {
+        let Some(group) = self.get_group(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid group_id {:?}", id)));
+        };
+        group.set_referees(referees);
+        Ok(())
+    }
+
+    pub fn mark_called(&mut self, id: &GroupId, time: NaiveDateTime) -> Result<(), Error> {
+        if !self.get_group_progress(id).calls.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "group {:?} was already called",
+                id
+            )));
+        }
+        self.update_progress(id, |x| x.calls.push(time))
+    }
+
+    pub fn recall(&mut self, id: &GroupId, time: NaiveDateTime) -> Result<(), Error> {
+        if self.get_group_progress(id).calls.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "group {:?} was not called yet",
+                id
+            )));
+        }
+        self.update_progress(id, |x| x.calls.push(time))
+    }
+
+    pub fn mark_no_show(&mut self, id: &GroupId, fencer_id: u32) -> Result<(), Error> {
+        let in_group = self
+            .get_group(id)
+            .is_some_and(|x| x.get_fencers().iter().any(|f| f.get_id() == fencer_id));
+        if !in_group {
+            return Err(Error::InvalidInput(format!(
+                "Ivalid fencer_id {:?}",
+                fencer_id
+            )));
+        }
+
+        self.update_progress(id, |x| {
+            if !x.no_shows.contains(&fencer_id) {
+                x.no_shows.push(fencer_id);
+            }
+        })
+    }
+}
@@ -0,0 +1,319 @@
+use chrono::NaiveDate;
+
+use crate::bewerb::Weapon;
+use crate::day::{default_day_start, Day};
+use crate::error::Error;
+use crate::timeslot::DEFAULT_TIMESLOT_MINUTES;
+use crate::tournament_core::Tournament;
+
+/// How a bewerb is fenced.
+#[derive(Clone, Debug)]
+pub struct Formula {
+    pub pool_rounds: u32,
+    /// fencers per pool aimed for
+    pub pool_size: u32,
+    /// share of fencers that reach the next pool round or the tableau
+    pub promoted_percent: u32,
+    /// whether a direct elimination tableau follows the pools
+    pub direct_elimination: bool,
+}
+
+/// A bewerb as it is expected from the registrations.
+#[derive(Clone, Debug)]
+pub struct BewerbDemand {
+    pub name: String,
+    pub weapon: Weapon,
+    pub expected_entries: u32,
+    pub formula: Formula,
+    pub date: NaiveDate,
+}
+
+#[derive(Clone, Debug)]
+pub struct CapacitySettings {
+    pub timeslot_minutes: u32,
+    /// pistes the venue can offer at most
+    pub max_pistes: u32,
+    /// timeslots a day can have at most
+    pub max_timeslots: u32,
+}
+
+impl Default for CapacitySettings {
+    fn default() -> Self {
+        Self {
+            timeslot_minutes: DEFAULT_TIMESLOT_MINUTES,
+            max_pistes: 16,
+            max_timeslots: 12,
+        }
+    }
+}
+
+/// Estimated pools of one round.
+#[derive(Clone, Debug)]
+pub struct RoundEstimate {
+    pub fencers: u32,
+    pub groups: u32,
+    /// length of the largest pool
+    pub group_minutes: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct BewerbEstimate {
+    pub name: String,
+    pub date: NaiveDate,
+    pub rounds: Vec<RoundEstimate>,
+    /// fencers in the tableau, 0 without direct elimination
+    pub de_fencers: u32,
+    pub de_bouts: u32,
+}
+
+/// A day to create with `create_days`.
+#[derive(Clone, Debug)]
+pub struct DayDraft {
+    pub date: NaiveDate,
+    pub n_ts: u32,
+    pub n_kp: u32,
+    pub timeslot_minutes: u32,
+    pub bewerbs: Vec<String>,
+}
+
+/// A day that does not fit into `max_timeslots` even with `max_pistes`.
+#[derive(Clone, Debug)]
+pub struct InfeasibleDay {
+    pub date: NaiveDate,
+    pub needed_timeslots: u32,
+    pub bewerbs: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CapacityPlan {
+    pub estimates: Vec<BewerbEstimate>,
+    pub days: Vec<DayDraft>,
+    pub infeasible: Vec<InfeasibleDay>,
+}
+
+impl BewerbDemand {
+    pub fn estimate(&self) -> BewerbEstimate {
+        let formula = &self.formula;
+        let pool_size = formula.pool_size.max(2);
+
+        let mut fencers = self.expected_entries;
+        let mut rounds = Vec::new();
+        for _ in 0..formula.pool_rounds {
+            if fencers == 0 {
+                break;
+            }
+
+            let groups = fencers.div_ceil(pool_size);
+            rounds.push(RoundEstimate {
+                fencers,
+                groups,
+                group_minutes: self.weapon.pool_minutes(fencers.div_ceil(groups)),
+            });
+            fencers = (fencers * formula.promoted_percent).div_ceil(100);
+        }
+
+        let de_fencers = if formula.direct_elimination {
+            fencers
+        } else {
+            0
+        };
+
+        BewerbEstimate {
+            name: self.name.clone(),
+            date: self.date,
+            rounds,
+            de_fencers,
+            de_bouts: de_fencers.saturating_sub(1),
+        }
+    }
+}
+
+impl BewerbEstimate {
+    /// Bouts of each tableau round, byes are left out.
+    fn de_rounds(&self) -> Vec<u32> {
+        let mut res = Vec::new();
+        let mut remaining = self.de_fencers;
+        while remaining > 1 {
+            let tableau = remaining.next_power_of_two();
+            let bouts = remaining - tableau / 2;
+            res.push(bouts);
+            remaining -= bouts;
+        }
+        res
+    }
+}
+
+/// Minutes the bewerbs of one day need with `n_kp` pistes: the larger of
+/// the piste time spread over all pistes and the longest bewerb fenced
+/// round after round.
+fn day_minutes(
+    estimates: &[&BewerbEstimate],
+    weapons: &[Weapon],
+    n_kp: u32,
+    timeslot_minutes: u32,
+    round_gap_minutes: u32,
+) -> u32 {
+    let mut area = 0;
+    let mut longest = 0;
+    for (estimate, weapon) in estimates.iter().zip(weapons) {
+        let mut chain = 0;
+        for (i, round) in estimate.rounds.iter().enumerate() {
+            if i > 0 {
+                chain += round_gap_minutes;
+            }
+            let group_slots = round.group_minutes.div_ceil(timeslot_minutes).max(1);
+            area += round.groups * group_slots * timeslot_minutes;
+            chain += round.groups.div_ceil(n_kp) * group_slots * timeslot_minutes;
+        }
+
+        let de_rounds = estimate.de_rounds();
+        if !de_rounds.is_empty() && !estimate.rounds.is_empty() {
+            chain += round_gap_minutes;
+        }
+        // every bout takes whole timeslots, as `dispatch_bouts` places them
+        let bout_slots = weapon.de_bout_minutes().div_ceil(timeslot_minutes).max(1);
+        for bouts in de_rounds {
+            area += bouts * bout_slots * timeslot_minutes;
+            chain += bouts.div_ceil(n_kp) * bout_slots * timeslot_minutes;
+        }
+        longest = longest.max(chain);
+    }
+
+    area.div_ceil(n_kp).max(longest)
+}
+
+impl Tournament {
+    /// Estimates pools and tableaus of the expected bewerbs and the fewest
+    /// pistes, and the timeslots then needed, for each of their days.
+    pub fn plan_capacity(
+        &self,
+        demands: &[BewerbDemand],
+        settings: &CapacitySettings,
+    ) -> CapacityPlan {
+        let mut res = CapacityPlan {
+            estimates: demands.iter().map(|x| x.estimate()).collect(),
+            ..Default::default()
+        };
+
+        let mut dates: Vec<NaiveDate> = demands.iter().map(|x| x.date).collect();
+        dates.sort();
+        dates.dedup();
+
+        let timeslot_minutes = settings.timeslot_minutes.max(1);
+        for date in dates {
+            let (estimates, weapons): (Vec<&BewerbEstimate>, Vec<Weapon>) = res
+                .estimates
+                .iter()
+                .zip(demands)
+                .filter(|(_, demand)| demand.date == date)
+                .map(|(estimate, demand)| (estimate, demand.weapon))
+                .unzip();
+            let bewerbs: Vec<String> = estimates.iter().map(|x| x.name.clone()).collect();
+
+            let needed_timeslots = |n_kp: u32| {
+                day_minutes(
+                    &estimates,
+                    &weapons,
+                    n_kp,
+                    timeslot_minutes,
+                    self.inner.round_gap_minutes,
+                )
+                .div_ceil(timeslot_minutes)
+                .max(1)
+            };
+
+            let fitting =
+                (1..=settings.max_pistes).find(|x| needed_timeslots(*x) <= settings.max_timeslots);
+            match fitting {
+                Some(n_kp) => res.days.push(DayDraft {
+                    date,
+                    n_ts: needed_timeslots(n_kp),
+                    n_kp,
+                    timeslot_minutes,
+                    bewerbs,
+                }),
+                None => res.infeasible.push(InfeasibleDay {
+                    date,
+                    needed_timeslots: needed_timeslots(settings.max_pistes.max(1)),
+                    bewerbs,
+                }),
+            }
+        }
+
+        res
+    }
+
+    /// Creates all days of the plan, or none if one of them does not fit,
+    /// its timeslots end after midnight, its date already has a day or is
+    /// outside the tournament. Returns the ids of the new days.
+    pub fn create_days(&mut self, plan: &CapacityPlan) -> Result<Vec<u32>, Error> {
+        if let Some(day) = plan.infeasible.first() {
+            return Err(Error::InvalidInput(format!(
+                "{} needs {} timeslots",
+                day.date, day.needed_timeslots
+            )));
+        }
+        for draft in plan.days.iter() {
+            self.check_day_date(None, draft.date)?;
+        }
+
+        let mut days = Vec::new();
+        for draft in plan.days.iter() {
+            let day = Day::new(
+                &mut self.inner.ids,
+                draft.date,
+                draft.n_ts,
+                draft.n_kp,
+                default_day_start(),
+                draft.timeslot_minutes,
+            )?;
+            days.push(day);
+        }
+
+        let mut res = Vec::new();
+        for day in days {
+            res.push(day.id);
+            self.days.insert(day);
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_timeslots_of_a_plan_fit_into_the_day() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 16).unwrap();
+        let demand = BewerbDemand {
+            name: "Foil".to_string(),
+            weapon: Weapon::Foil,
+            expected_entries: 42,
+            formula: Formula {
+                pool_rounds: 1,
+                pool_size: 7,
+                promoted_percent: 100,
+                direct_elimination: false,
+            },
+            date,
+        };
+        let settings = CapacitySettings {
+            timeslot_minutes: 30,
+            max_pistes: 1,
+            max_timeslots: 30,
+        };
+
+        let mut tournament = Tournament::new();
+        let plan = tournament.plan_capacity(&[demand], &settings);
+        let draft = &plan.days[0];
+        // more timeslots than 60 minutes ones from 09:00 would allow
+        assert!(draft.n_ts > 15, "{} timeslots", draft.n_ts);
+
+        let ids = tournament.create_days(&plan).unwrap();
+        let day = tournament.days.get(ids[0]).unwrap();
+        assert_eq!(day.timeslots().count(), draft.n_ts as usize);
+        assert!(day.timeslots().all(|x| x.duration == 30));
+    }
+}
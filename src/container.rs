@@ -1,50 +1,93 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::slice::{Iter, IterMut};
+use std::sync::Arc;
 
+/// Ids are handed out by `IdAllocator` when an item is created and never
+/// change afterwards, also not once the item is shared in an `Arc`.
 pub trait HasId {
     fn get_id(&self) -> u32;
-    fn set_id(&mut self, id: u32);
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
-pub struct UidContainer<T> {
-    vec: Vec<T>,
+impl<T: HasId> HasId for Arc<T> {
+    fn get_id(&self) -> u32 {
+        self.as_ref().get_id()
+    }
 }
 
-impl<T: HasId> UidContainer<T> {
-    pub fn insert(&mut self, t: T) {
-        self.vec.push(t);
-    }
+/// Hands out the ids of all entities of a tournament.
+///
+/// The high-water mark is saved with the tournament, so an id is never
+/// handed out twice, not even after the entity holding it was removed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct IdAllocator {
+    next_id: u32,
+}
 
-    fn get_new_id(&self) -> u32 {
-        let mut id = 0;
-        let ids: Vec<u32> = self.vec.iter().map(|x| x.get_id()).collect();
-        while ids.contains(&id) {
-            id += 1;
-        }
+impl IdAllocator {
+    pub fn next(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
         id
     }
 
-    pub fn get_next_id(&self) -> u32 {
-        self.get_new_id()
+    /// Makes sure `id`, e.g. from a file saved without a high-water mark,
+    /// is not handed out again.
+    pub fn reserve(&mut self, id: u32) {
+        self.next_id = self.next_id.max(id.saturating_add(1));
     }
+}
 
-    pub fn push(&mut self, mut item: T) {
-        let id = self.get_new_id();
-        item.set_id(id);
-        self.vec.push(item);
+/// Items in insertion order with an index from id to position.
+///
+/// The ids of items must not be changed through `iter_mut`.
+#[derive(Debug)]
+pub struct UidContainer<T> {
+    vec: Vec<T>,
+    index: HashMap<u32, usize>,
+}
+
+impl<T> Default for UidContainer<T> {
+    fn default() -> Self {
+        Self {
+            vec: Vec::new(),
+            index: HashMap::new(),
+        }
     }
+}
 
+impl<T: HasId> UidContainer<T> {
+    /// Inserts an item keeping its id, an item with the same id is replaced.
+    pub fn insert(&mut self, t: T) {
+        let id = t.get_id();
+        if let Some(&pos) = self.index.get(&id) {
+            self.vec[pos] = t;
+            return;
+        }
+
+        self.index.insert(id, self.vec.len());
+        self.vec.push(t);
+    }
+
+    /// Keeps the order of the other items, so the ones after the removed
+    /// item move up and only their positions in the index are updated.
     pub fn remove(&mut self, id: u32) {
-        self.vec.retain(|x| x.get_id() != id);
+        let Some(pos) = self.index.remove(&id) else {
+            return;
+        };
+
+        self.vec.remove(pos);
+        for (i, item) in self.vec.iter().enumerate().skip(pos) {
+            self.index.insert(item.get_id(), i);
+        }
     }
 
     pub fn get(&self, id: u32) -> Option<&T> {
-        self.iter().find(|x| x.get_id() == id)
+        self.index.get(&id).map(|&pos| &self.vec[pos])
     }
 
     pub fn get_mut(&mut self, id: u32) -> Option<&mut T> {
-        self.iter_mut().find(|x| x.get_id() == id)
+        self.index.get(&id).map(|&pos| &mut self.vec[pos])
     }
 }
 
@@ -57,3 +100,51 @@ impl<T> UidContainer<T> {
         self.vec.iter_mut()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_moves_past_the_id() {
+        let mut ids = IdAllocator::default();
+        ids.reserve(7);
+        assert_eq!(ids.next(), 8);
+
+        ids.reserve(3);
+        assert_eq!(ids.next(), 9);
+
+        ids.reserve(u32::MAX);
+        assert_eq!(ids.next_id, u32::MAX);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Item(u32);
+
+    impl HasId for Item {
+        fn get_id(&self) -> u32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn remove_keeps_the_others_findable() {
+        let mut items = UidContainer::default();
+        for id in [4, 7, 2, 9] {
+            items.insert(Item(id));
+        }
+
+        items.remove(7);
+        items.remove(5);
+        assert_eq!(items.get(7), None);
+        for id in [4, 2, 9] {
+            assert_eq!(items.get(id), Some(&Item(id)));
+        }
+        let order: Vec<u32> = items.iter().map(|x| x.0).collect();
+        assert_eq!(order, vec![4, 2, 9]);
+
+        items.remove(4);
+        assert_eq!(items.get_mut(9), Some(&mut Item(9)));
+        assert_eq!(items.get(2), Some(&Item(2)));
+    }
+}
@@ -1,29 +1,81 @@
-use chrono::{NaiveDate, NaiveDateTime};
-use prost_types::Timestamp;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::slice::Iter;
 use std::sync::Arc;
 
 use crate::arena_slot::{ArenaSlot, ArenaSlotId};
 use crate::bewerb::Bewerb;
-use crate::container::{HasId, UidContainer};
-use crate::timeslot::{Timeslot, TimeslotId, TimeslotSaveable};
+use crate::container::{HasId, IdAllocator, UidContainer};
+use crate::error::Error;
+use crate::time_zone::{date_to_timestamp, timestamp_to_date};
+use crate::timeslot::{Timeslot, TimeslotId, TimeslotSaveable, DEFAULT_TIMESLOT_MINUTES};
 use crate::tournament::{DayData, SimpleDay};
 
+pub(crate) fn default_day_start() -> NaiveTime {
+    NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+}
+
+/// Start and length of the timeslots of a day created without times: the
+/// default length from 09:00 if they end by midnight, otherwise starting
+/// earlier and, if that is not enough either, shorter.
+fn default_timeslots(n_ts: u32) -> (NaiveTime, u32) {
+    const DAY_MINUTES: u32 = 24 * 60;
+    let minutes = DEFAULT_TIMESLOT_MINUTES
+        .min(DAY_MINUTES / n_ts.max(1))
+        .max(1);
+    let latest_start = DAY_MINUTES.saturating_sub(n_ts.saturating_mul(minutes));
+    let start = default_day_start().num_seconds_from_midnight() / 60;
+    let start = NaiveTime::MIN + TimeDelta::minutes(start.min(latest_start) as i64);
+    (start, minutes)
+}
+
+/// A time of the day without any timeslot, e.g. the lunch break.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DayBreak {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub reason: String,
+}
+
+impl DayBreak {
+    /// Whether the break on `date` overlaps the time from `start` to `end`.
+    pub fn overlaps(&self, date: NaiveDate, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        start < date.and_time(self.end) && date.and_time(self.start) < end
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Day {
     pub id: u32,
     date: NaiveDate,
+    /// opening time of the venue
+    start: NaiveTime,
+    /// closing time of the venue, open end if not set
+    close: Option<NaiveTime>,
+    /// ordered by start
+    breaks: Vec<DayBreak>,
     n_ts: u32,
     n_kp: u32,
-    timeslots: Vec<Timeslot>,
+    /// pistes available on this day, all if empty
+    pub enabled_pistes: Vec<u32>,
+    timeslots: UidContainer<Timeslot>,
 }
 
 #[derive(Clone, Default, Debug, Deserialize, Serialize)]
 pub struct DaySaveable {
     pub id: u32,
     date: NaiveDate,
+    #[serde(default = "default_day_start")]
+    start: NaiveTime,
+    #[serde(default)]
+    close: Option<NaiveTime>,
+    #[serde(default)]
+    breaks: Vec<DayBreak>,
     n_ts: u32,
     n_kp: u32,
+    #[serde(default)]
+    enabled_pistes: Vec<u32>,
     timeslots: Vec<TimeslotSaveable>,
 }
 
@@ -34,8 +86,12 @@ impl From<&Day> for DaySaveable {
         Self {
             id: day.id,
             date: day.date,
+            start: day.start,
+            close: day.close,
+            breaks: day.breaks.clone(),
             n_ts: day.n_ts,
             n_kp: day.n_kp,
+            enabled_pistes: day.enabled_pistes.clone(),
             timeslots,
         }
     }
@@ -43,57 +99,459 @@ impl From<&Day> for DaySaveable {
 
 impl Day {
     pub fn from_saveable(day: DaySaveable, bewerbs: &mut UidContainer<Bewerb>) -> Self {
-        let mut timeslots: Vec<Timeslot> = Default::default();
+        let mut timeslots: UidContainer<Timeslot> = Default::default();
+        let mut start = day.start;
         for ts in day.timeslots.iter() {
-            timeslots.push(Timeslot::from_timeslot_saveable(ts.clone(), bewerbs));
+            let ts = Timeslot::from_timeslot_saveable(ts.clone(), start, bewerbs);
+            start = ts.end(day.date).time();
+            timeslots.insert(ts);
         }
 
         Self {
             id: day.id,
             date: day.date,
+            start: day.start,
+            close: day.close,
+            breaks: day.breaks,
             n_ts: day.n_ts,
             n_kp: day.n_kp,
+            enabled_pistes: day.enabled_pistes,
             timeslots,
         }
     }
 
-    pub fn new(id: u32, date: NaiveDate, n_ts: u32, n_kp: u32) -> Self {
+    /// Lays out `n_ts` timeslots of `minutes` length back to back from
+    /// `start`. Fails if they would end after midnight.
+    pub fn new(
+        ids: &mut IdAllocator,
+        date: NaiveDate,
+        n_ts: u32,
+        n_kp: u32,
+        start: NaiveTime,
+        minutes: u32,
+    ) -> Result<Self, Error> {
         let mut res = Self {
-            id,
+            id: ids.next(),
             date,
+            start,
             n_ts,
             n_kp,
             ..Default::default()
         };
 
-        for i in 0..n_ts {
+        let durations = vec![minutes; n_ts as usize];
+        let starts = res.layout(date.and_time(start), None, &[], &durations)?;
+        for start in starts {
             let tid = TimeslotId {
                 day_id: res.id,
-                timeslot_id: i,
+                timeslot_id: ids.next(),
             };
 
-            let ts = Timeslot::new(tid, n_kp);
-            res.timeslots.push(ts);
+            let ts = Timeslot::new(ids, tid, n_kp, start, minutes);
+            res.timeslots.insert(ts);
         }
-        res
+        Ok(res)
     }
 
-    pub fn get_arena(&mut self, id: &ArenaSlotId) -> Option<Arc<ArenaSlot>> {
-        let ts = self
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Closing time on the date of the day, midnight after it if the day
+    /// has an open end.
+    fn end_of_day(&self, close: Option<NaiveTime>) -> NaiveDateTime {
+        match close {
+            Some(close) => self.date.and_time(close),
+            None => self.date.and_time(NaiveTime::MIN) + TimeDelta::days(1),
+        }
+    }
+
+    /// The earliest start from `start` on for a timeslot of `minutes` length
+    /// that does not overlap one of the breaks.
+    fn skip_breaks(
+        &self,
+        breaks: &[DayBreak],
+        start: NaiveDateTime,
+        minutes: u32,
+    ) -> NaiveDateTime {
+        let duration = TimeDelta::minutes(minutes as i64);
+        let mut start = start;
+        while let Some(brk) = breaks
+            .iter()
+            .find(|x| x.overlaps(self.date, start, start + duration))
+        {
+            start = self.date.and_time(brk.end);
+        }
+        start
+    }
+
+    /// Starts of timeslots with the durations laid out back to back from
+    /// `start` around the breaks. Fails if the last one ends after closing
+    /// or, with an open end, after midnight.
+    fn layout(
+        &self,
+        start: NaiveDateTime,
+        close: Option<NaiveTime>,
+        breaks: &[DayBreak],
+        durations: &[u32],
+    ) -> Result<Vec<NaiveTime>, Error> {
+        let end_of_day = self.end_of_day(close);
+        let mut res = Vec::new();
+        let mut start = start;
+        for duration in durations {
+            start = self.skip_breaks(breaks, start, *duration);
+            let end = start + TimeDelta::minutes(*duration as i64);
+            if end > end_of_day {
+                return Err(Error::InvalidInput(format!(
+                    "timeslots of day {:?} end at {} after closing or midnight",
+                    self.id, end
+                )));
+            }
+            res.push(start.time());
+            start = end;
+        }
+        Ok(res)
+    }
+
+    pub fn set_date(&mut self, date: NaiveDate) {
+        self.date = date;
+    }
+
+    /// Arena slots that `reshape` with `n_ts` and `n_kp` would remove.
+    pub fn get_arenas_outside(&self, n_ts: u32, n_kp: u32) -> Vec<Arc<ArenaSlot>> {
+        self.timeslots
+            .iter()
+            .enumerate()
+            .flat_map(|(i, ts)| {
+                ts.get_all_arenas()
+                    .into_iter()
+                    .filter(move |x| i as u32 >= n_ts || x.piste >= n_kp)
+            })
+            .collect()
+    }
+
+    /// Changes the number of timeslots and pistes. Timeslots are added or
+    /// removed at the end of the day, after the breaks, pistes at the
+    /// highest numbers. Arena slots that remain keep their ids and groups.
+    /// Fails without a change if added timeslots would end after closing.
+    pub fn reshape(&mut self, ids: &mut IdAllocator, n_ts: u32, n_kp: u32) -> Result<(), Error> {
+        let kept: Vec<u32> = self
+            .timeslots
+            .iter()
+            .take(n_ts as usize)
+            .map(|x| x.get_id())
+            .collect();
+        let start = kept
+            .last()
+            .and_then(|x| self.get_timeslot_range(*x))
+            .map_or(self.date.and_time(self.start), |(_, end)| end);
+        let added = vec![DEFAULT_TIMESLOT_MINUTES; (n_ts as usize).saturating_sub(kept.len())];
+        let starts = self.layout(start, self.close, &self.breaks, &added)?;
+
+        let removed: Vec<u32> = self
             .timeslots
             .iter()
-            .find(|x| x.id.timeslot_id == id.timeslot_id)?;
+            .skip(n_ts as usize)
+            .map(|x| x.get_id())
+            .collect();
+        for id in removed {
+            self.timeslots.remove(id);
+        }
+
+        for ts in self.timeslots.iter_mut() {
+            ts.set_n_kp(ids, n_kp);
+        }
+
+        for start in starts {
+            let tid = TimeslotId {
+                day_id: self.id,
+                timeslot_id: ids.next(),
+            };
+
+            let ts = Timeslot::new(ids, tid, n_kp, start, DEFAULT_TIMESLOT_MINUTES);
+            self.timeslots.insert(ts);
+        }
+
+        self.n_ts = n_ts;
+        self.n_kp = n_kp;
+        Ok(())
+    }
+
+    pub fn start(&self) -> NaiveTime {
+        self.start
+    }
+
+    /// Moves the opening of the day, the breaks keep their offset to it and
+    /// the timeslots are laid out again from it, see `set_hours`.
+    pub fn set_start(&mut self, start: NaiveTime) -> Result<(), Error> {
+        let delta = start - self.start;
+        let mut breaks = Vec::new();
+        for brk in self.breaks.iter() {
+            let brk_start = self.date.and_time(brk.start) + delta;
+            let brk_end = self.date.and_time(brk.end) + delta;
+            if brk_start.date() != self.date || brk_end.date() != self.date {
+                return Err(Error::InvalidInput(format!(
+                    "break {:?} would move out of day {:?}",
+                    brk.reason, self.id
+                )));
+            }
+            breaks.push(DayBreak {
+                start: brk_start.time(),
+                end: brk_end.time(),
+                reason: brk.reason.clone(),
+            });
+        }
 
+        self.set_hours(start, self.close, breaks)
+    }
+
+    /// Lays out all timeslots back to back from the start of the day with
+    /// the same length, leaving out the breaks. Fails if they do not end
+    /// before closing.
+    pub fn set_timeslot_minutes(&mut self, minutes: u32) -> Result<(), Error> {
+        let durations = vec![minutes; self.timeslots.iter().count()];
+        let start = self.date.and_time(self.start);
+        let starts = self.layout(start, self.close, &self.breaks, &durations)?;
+        for (ts, start) in self.timeslots.iter_mut().zip(starts) {
+            ts.start = start;
+            ts.duration = minutes;
+        }
+        Ok(())
+    }
+
+    pub fn close(&self) -> Option<NaiveTime> {
+        self.close
+    }
+
+    pub fn breaks(&self) -> &[DayBreak] {
+        &self.breaks
+    }
+
+    /// Sets opening and closing time and the breaks of the day and lays out
+    /// the timeslots again with their lengths from the opening on, leaving
+    /// out the breaks. Fails if a break is outside the opening hours, two
+    /// breaks overlap or the timeslots do not end before closing.
+    pub fn set_hours(
+        &mut self,
+        open: NaiveTime,
+        close: Option<NaiveTime>,
+        mut breaks: Vec<DayBreak>,
+    ) -> Result<(), Error> {
+        if close.is_some_and(|x| x <= open) {
+            return Err(Error::InvalidInput(format!(
+                "day {:?} has to close after it opens",
+                self.id
+            )));
+        }
+
+        breaks.sort_by_key(|x| x.start);
+        for (i, brk) in breaks.iter().enumerate() {
+            let outside = brk.start < open || close.is_some_and(|x| brk.end > x);
+            if brk.end <= brk.start || outside {
+                return Err(Error::InvalidInput(format!(
+                    "break {:?} has to be within the opening hours",
+                    brk.reason
+                )));
+            }
+            if i > 0 && breaks[i - 1].end > brk.start {
+                return Err(Error::InvalidInput(format!(
+                    "break {:?} overlaps the break before",
+                    brk.reason
+                )));
+            }
+        }
+
+        let durations: Vec<u32> = self.timeslots.iter().map(|x| x.duration).collect();
+        let starts = self.layout(self.date.and_time(open), close, &breaks, &durations)?;
+
+        for (ts, start) in self.timeslots.iter_mut().zip(starts) {
+            ts.start = start;
+        }
+        self.start = open;
+        self.close = close;
+        self.breaks = breaks;
+        Ok(())
+    }
+
+    /// Timeslots starting before opening, ending after closing or
+    /// overlapping a break, with the break if there is one.
+    pub fn get_timeslots_outside_hours(&self) -> Vec<(u32, Option<DayBreak>)> {
+        let end_of_day = self.end_of_day(self.close);
+        let mut res = Vec::new();
+        for ts in self.timeslots.iter() {
+            let Some((start, end)) = self.get_timeslot_range(ts.get_id()) else {
+                continue;
+            };
+            if let Some(brk) = self
+                .breaks
+                .iter()
+                .find(|x| x.overlaps(self.date, start, end))
+            {
+                res.push((ts.get_id(), Some(brk.clone())));
+            } else if ts.start < self.start || end > end_of_day {
+                res.push((ts.get_id(), None));
+            }
+        }
+        res
+    }
+
+    /// Sets start and duration of one timeslot, it must neither overlap
+    /// the timeslot before nor the one after it.
+    pub fn set_timeslot_time(
+        &mut self,
+        timeslot_id: u32,
+        start: NaiveTime,
+        duration: u32,
+    ) -> Result<(), Error> {
+        let timeslots: Vec<&Timeslot> = self.timeslots.iter().collect();
+        let Some(pos) = timeslots.iter().position(|x| x.get_id() == timeslot_id) else {
+            return Err(Error::InvalidInput(format!(
+                "Ivalid timeslot_id {:?}",
+                timeslot_id
+            )));
+        };
+
+        let start_at = self.date.and_time(start);
+        let end = start_at + TimeDelta::minutes(duration as i64);
+        if start < self.start || end > self.end_of_day(self.close) {
+            return Err(Error::InvalidInput(format!(
+                "timeslot {:?} has to be within the day",
+                timeslot_id
+            )));
+        }
+        if let Some(brk) = self
+            .breaks
+            .iter()
+            .find(|x| x.overlaps(self.date, start_at, end))
+        {
+            return Err(Error::InvalidInput(format!(
+                "timeslot {:?} overlaps the break {:?}",
+                timeslot_id, brk.reason
+            )));
+        }
+        let previous_end = pos
+            .checked_sub(1)
+            .and_then(|x| self.get_timeslot_range(timeslots[x].get_id()));
+        if previous_end.is_some_and(|(_, end)| end > start_at) {
+            return Err(Error::InvalidInput(format!(
+                "timeslot {:?} overlaps the timeslot before",
+                timeslot_id
+            )));
+        }
+        if timeslots
+            .get(pos + 1)
+            .is_some_and(|x| self.date.and_time(x.start) < end)
+        {
+            return Err(Error::InvalidInput(format!(
+                "timeslot {:?} overlaps the timeslot after",
+                timeslot_id
+            )));
+        }
+
+        let ts = self.timeslots.get_mut(timeslot_id).unwrap();
+        ts.start = start;
+        ts.duration = duration;
+        Ok(())
+    }
+
+    /// Adds a break of `minutes` length where the timeslot starts, it and
+    /// all later timeslots are laid out again after it, see `set_hours`.
+    pub fn add_break(
+        &mut self,
+        timeslot_id: u32,
+        minutes: u32,
+        reason: String,
+    ) -> Result<(), Error> {
+        let Some(ts) = self.timeslots.get(timeslot_id) else {
+            return Err(Error::InvalidInput(format!(
+                "Ivalid timeslot_id {:?}",
+                timeslot_id
+            )));
+        };
+
+        let start = self.date.and_time(ts.start);
+        let end = start + TimeDelta::minutes(minutes as i64);
+        if end.date() != self.date {
+            return Err(Error::InvalidInput(format!(
+                "break {:?} has to end before midnight",
+                reason
+            )));
+        }
+
+        let mut breaks = self.breaks.clone();
+        breaks.push(DayBreak {
+            start: start.time(),
+            end: end.time(),
+            reason,
+        });
+        self.set_hours(self.start, self.close, breaks)
+    }
+
+    pub fn timeslots(&self) -> Iter<'_, Timeslot> {
+        self.timeslots.iter()
+    }
+
+    pub fn get_timeslot(&self, timeslot_id: u32) -> Option<&Timeslot> {
+        self.timeslots.get(timeslot_id)
+    }
+
+    pub fn get_timeslot_mut(&mut self, timeslot_id: u32) -> Option<&mut Timeslot> {
+        self.timeslots.get_mut(timeslot_id)
+    }
+
+    /// The timeslot's own pistes decide if it has any, otherwise the day's.
+    pub fn is_piste_enabled(&self, timeslot_id: u32, piste: u32) -> bool {
+        let enabled = match self.timeslots.get(timeslot_id) {
+            Some(ts) if !ts.enabled_pistes.is_empty() => &ts.enabled_pistes,
+            Some(_) => &self.enabled_pistes,
+            None => return false,
+        };
+        enabled.is_empty() || enabled.contains(&piste)
+    }
+
+    pub fn get_timeslot_at(&self, time: NaiveDateTime) -> Option<&Timeslot> {
+        self.timeslots.iter().find(|x| x.contains(self.date, time))
+    }
+
+    /// Start and end of a timeslot as date and time.
+    pub fn get_timeslot_range(&self, timeslot_id: u32) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let ts = self.timeslots.get(timeslot_id)?;
+        Some((self.date.and_time(ts.start), ts.end(self.date)))
+    }
+
+    pub fn get_arena(&mut self, id: &ArenaSlotId) -> Option<Arc<ArenaSlot>> {
+        let ts = self.timeslots.get(id.timeslot_id)?;
         ts.get_arena(id)
     }
 
-    pub fn from(id: u32, sday: SimpleDay) -> Self {
+    pub fn get_all_arenas(&self) -> Vec<Arc<ArenaSlot>> {
+        self.timeslots
+            .iter()
+            .flat_map(|x| x.get_all_arenas())
+            .collect()
+    }
+
+    /// The date of `sday` is taken as the local date in `tz`, the timeslots
+    /// are laid out as by `default_timeslots`.
+    pub fn from(ids: &mut IdAllocator, sday: SimpleDay, tz: Tz) -> Result<Self, Error> {
         let date = match sday.date {
-            Some(date) => NaiveDateTime::from_timestamp(date.seconds, 0).date(),
-            None => NaiveDateTime::from_timestamp(0, 0).date(),
+            Some(date) => timestamp_to_date(&date, tz)?,
+            None => NaiveDate::default(),
         };
 
-        Self::new(id, date, sday.number_time_slots, sday.number_arenas)
+        let n_ts = sday.number_time_slots;
+        let (start, minutes) = default_timeslots(n_ts);
+        Self::new(ids, date, n_ts, sday.number_arenas, start, minutes)
+    }
+
+    /// The date is sent as the instant the day starts in `tz`.
+    pub fn to_simple_day(&self, tz: Tz) -> SimpleDay {
+        SimpleDay {
+            id: self.id,
+            date: Some(date_to_timestamp(self.date, tz)),
+            number_time_slots: self.n_ts,
+            number_arenas: self.n_kp,
+        }
     }
 }
 
@@ -109,23 +567,52 @@ impl HasId for Day {
     fn get_id(&self) -> u32 {
         self.id
     }
-
-    fn set_id(&mut self, id: u32) {
-        self.id = id;
-    }
 }
 
-impl From<&Day> for SimpleDay {
-    fn from(val: &Day) -> Self {
-        let date = Some(Timestamp {
-            seconds: NaiveDateTime::from(val.date).timestamp(),
-            nanos: 0,
-        });
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time_zone::date_to_timestamp;
+
+    fn simple_day(n_ts: u32) -> SimpleDay {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 16).unwrap();
         SimpleDay {
-            id: val.id,
-            date,
-            number_time_slots: val.n_ts,
-            number_arenas: val.n_kp,
+            id: 0,
+            date: Some(date_to_timestamp(date, Tz::UTC)),
+            number_time_slots: n_ts,
+            number_arenas: 2,
+        }
+    }
+
+    fn last_end(day: &Day) -> NaiveDateTime {
+        let last = day.timeslots().last().unwrap();
+        day.get_timeslot_range(last.get_id()).unwrap().1
+    }
+
+    #[test]
+    fn default_day_starts_at_nine() {
+        let day = Day::from(&mut IdAllocator::default(), simple_day(8), Tz::UTC).unwrap();
+        let first = day.timeslots().next().unwrap();
+        assert_eq!(first.start, default_day_start());
+        assert_eq!(first.duration, DEFAULT_TIMESLOT_MINUTES);
+        assert_eq!(day.timeslots().count(), 8);
+    }
+
+    #[test]
+    fn many_timeslots_fit_before_midnight() {
+        for n_ts in [20, 24, 30, 100] {
+            let day = Day::from(&mut IdAllocator::default(), simple_day(n_ts), Tz::UTC).unwrap();
+            assert_eq!(day.timeslots().count(), n_ts as usize);
+            let midnight = day.date().succ_opt().unwrap().and_time(NaiveTime::MIN);
+            assert!(last_end(&day) <= midnight, "{} timeslots", n_ts);
         }
     }
+
+    #[test]
+    fn new_rejects_timeslots_past_midnight() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 16).unwrap();
+        let start = NaiveTime::from_hms_opt(20, 0, 0).unwrap();
+        assert!(Day::new(&mut IdAllocator::default(), date, 5, 2, start, 60).is_err());
+        assert!(Day::new(&mut IdAllocator::default(), date, 4, 2, start, 60).is_ok());
+    }
 }
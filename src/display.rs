@@ -0,0 +1,145 @@
+use chrono::NaiveDateTime;
+
+use crate::container::HasId;
+use crate::elimination::BoutPlacement;
+use crate::error::Error;
+use crate::group::{GroupId, GroupProgress};
+use crate::schedule::Placement;
+use crate::tableau::{BoutId, BoutState};
+use crate::tournament_core::Tournament;
+
+/// What is fenced on the piste.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PisteItem {
+    Group(GroupId),
+    /// an elimination bout with the name of its table, e.g. "T32"
+    Bout {
+        bout: BoutId,
+        table: String,
+    },
+}
+
+/// How far the group or bout on the piste has come.
+#[derive(Clone, Debug)]
+pub enum PisteState {
+    Group(GroupProgress),
+    /// fencers, score and times of the bout
+    Bout(BoutState),
+}
+
+/// What a piste screen shows about one group or bout.
+#[derive(Clone, Debug)]
+pub struct PisteEntry {
+    pub item: PisteItem,
+    /// expected times, see `expected_start` and `expected_end`
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub started: bool,
+    pub state: PisteState,
+    /// id and name of each fencer
+    pub fencers: Vec<(u32, String)>,
+    pub referees: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PisteDisplay {
+    pub day_id: u32,
+    pub piste: u32,
+    pub piste_name: String,
+    pub now: Option<PisteEntry>,
+    pub next: Option<PisteEntry>,
+}
+
+impl Tournament {
+    /// `None` once the group is finished.
+    fn get_group_entry(&self, placement: &Placement) -> Option<PisteEntry> {
+        let group = self.get_group(&placement.group)?;
+        let progress = group.get_progress();
+        if progress.actual_end.is_some() {
+            return None;
+        }
+
+        Some(PisteEntry {
+            item: PisteItem::Group(placement.group.clone()),
+            start: self.expected_start(placement),
+            end: self.expected_end(placement),
+            started: progress.actual_start.is_some(),
+            state: PisteState::Group(progress),
+            fencers: group
+                .get_fencers()
+                .iter()
+                .map(|x| (x.get_id(), x.name()))
+                .collect(),
+            referees: group.get_referees(),
+        })
+    }
+
+    /// `None` once the bout is decided.
+    fn get_bout_entry(&self, placement: &BoutPlacement) -> Option<PisteEntry> {
+        let state = self.get_bout(&placement.bout)?.get_state();
+        if state.winner.is_some() {
+            return None;
+        }
+
+        Some(PisteEntry {
+            item: PisteItem::Bout {
+                bout: placement.bout.clone(),
+                table: placement.table.clone(),
+            },
+            start: self.expected_bout_start(placement),
+            end: self.expected_bout_end(placement),
+            started: state.actual_start.is_some(),
+            state: PisteState::Bout(state),
+            fencers: placement
+                .fencers
+                .iter()
+                .map(|x| {
+                    let name = self.fencers.get(*x).map(|f| f.name()).unwrap_or_default();
+                    (*x, name)
+                })
+                .collect(),
+            referees: Vec::new(),
+        })
+    }
+
+    /// The group or bout on the piste at `now` and the one after it. One
+    /// counts as current from its start until it is finished, if it is late
+    /// even past its expected end.
+    pub fn get_piste_display(
+        &self,
+        day_id: u32,
+        piste: u32,
+        now: NaiveDateTime,
+    ) -> Result<PisteDisplay, Error> {
+        if self.days.get(day_id).is_none() {
+            return Err(Error::InvalidInput(format!("Ivalid day_id {:?}", day_id)));
+        }
+
+        let groups = self
+            .get_placements()
+            .into_iter()
+            .filter(|x| x.slot.arena.day_id == day_id && x.slot.piste == piste)
+            .filter_map(|x| self.get_group_entry(&x));
+        let bouts = self
+            .get_bout_placements()
+            .into_iter()
+            .filter(|x| x.slot.arena.day_id == day_id && x.slot.piste == piste)
+            .filter_map(|x| self.get_bout_entry(&x));
+        let mut entries: Vec<PisteEntry> = groups.chain(bouts).collect();
+        entries.sort_by_key(|x| x.start);
+
+        let current = entries.iter().position(|x| x.started || x.start <= now);
+        let next = match current {
+            Some(pos) => pos + 1,
+            None => 0,
+        };
+
+        Ok(PisteDisplay {
+            day_id,
+            piste,
+            piste_name: self.piste_name(piste),
+            now: current.and_then(|x| entries.get(x).cloned()),
+            next: entries.get(next).cloned(),
+        })
+    }
+}
@@ -0,0 +1,376 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+use crate::arena_slot::ArenaSlotId;
+use crate::error::Error;
+use crate::schedule::Slot;
+use crate::tableau::{Bout, BoutId, Tableau};
+use crate::tournament_core::Tournament;
+
+/// Where and when an elimination bout is fenced.
+#[derive(Clone, Debug)]
+pub struct BoutPlacement {
+    pub bout: BoutId,
+    /// name of the table, e.g. "T32"
+    pub table: String,
+    pub fencers: Vec<u32>,
+    pub winner: Option<u32>,
+    /// the whole time the bout occupies, `slot.arena` is the first slot
+    pub slot: Slot,
+    pub arenas: Vec<ArenaSlotId>,
+}
+
+impl Tournament {
+    pub fn get_bout(&self, id: &BoutId) -> Option<Arc<Bout>> {
+        self.bewerbs.get(id.bewerb_id)?.get_bout(id)
+    }
+
+    /// Arena slots used by elimination bouts.
+    pub(crate) fn get_bout_arenas(&self) -> Vec<ArenaSlotId> {
+        self.all_bouts()
+            .iter()
+            .flat_map(|x| x.get_arenas())
+            .map(|x| x.id().clone())
+            .collect()
+    }
+
+    fn get_bout_placement(&self, bout: &Bout) -> Option<BoutPlacement> {
+        let run: Vec<Slot> = bout
+            .get_arenas()
+            .iter()
+            .filter_map(|x| self.get_slot(x.id()))
+            .collect();
+
+        Some(BoutPlacement {
+            bout: bout.id(),
+            table: bout.table_name(),
+            fencers: bout.get_fencers(),
+            winner: bout.get_state().winner,
+            slot: Slot::span(&run)?,
+            arenas: run.iter().map(|x| x.arena.clone()).collect(),
+        })
+    }
+
+    /// All bouts that are put into an arena slot, ordered by start.
+    pub fn get_bout_placements(&self) -> Vec<BoutPlacement> {
+        let mut res: Vec<BoutPlacement> = self
+            .all_bouts()
+            .iter()
+            .filter_map(|x| self.get_bout_placement(x))
+            .collect();
+        res.sort_by_key(|x| (x.slot.start, x.slot.piste));
+        res
+    }
+
+    /// Actual start if the bout has started, planned start otherwise.
+    pub fn expected_bout_start(&self, placement: &BoutPlacement) -> NaiveDateTime {
+        self.get_bout(&placement.bout)
+            .and_then(|x| x.get_state().actual_start)
+            .unwrap_or(placement.slot.start)
+    }
+
+    /// Actual end if the bout is decided, otherwise the planned end moved by
+    /// a late start.
+    pub fn expected_bout_end(&self, placement: &BoutPlacement) -> NaiveDateTime {
+        let state = self
+            .get_bout(&placement.bout)
+            .map(|x| x.get_state())
+            .unwrap_or_default();
+        if let Some(end) = state.actual_end {
+            return end;
+        }
+
+        let late_start = state
+            .actual_start
+            .map(|x| (x - placement.slot.start).max(TimeDelta::zero()))
+            .unwrap_or_default();
+        placement.slot.end + late_start
+    }
+
+    /// Creates the tableau of the bewerb for the fencers in order of their
+    /// seeding and replaces the previous one. Fails once a bout of the
+    /// previous tableau has started.
+    pub fn create_tableau(&mut self, bewerb_id: u32, seeding: Vec<u32>) -> Result<(), Error> {
+        let Some(bewerb) = self.bewerbs.get(bewerb_id) else {
+            return Err(Error::InvalidInput(format!(
+                "Ivalid bewerb_id {:?}",
+                bewerb_id
+            )));
+        };
+
+        if seeding.len() < 2 {
+            return Err(Error::InvalidInput(
+                "a tableau needs at least two fencers".to_string(),
+            ));
+        }
+        for (i, fencer_id) in seeding.iter().enumerate() {
+            let known = self
+                .fencers
+                .get(*fencer_id)
+                .is_some_and(|x| x.is_in_bewerb(bewerb_id));
+            if !known || seeding[..i].contains(fencer_id) {
+                return Err(Error::InvalidInput(format!(
+                    "Ivalid fencer_id {:?}",
+                    fencer_id
+                )));
+            }
+        }
+
+        let old = bewerb.get_tableau().bouts();
+        if old.iter().any(|x| x.get_state().actual_start.is_some()) {
+            return Err(Error::InvalidInput(format!(
+                "tableau of bewerb {:?} has already started",
+                bewerb_id
+            )));
+        }
+        for bout in old {
+            Self::freeup_bout(bout);
+        }
+
+        let tableau = Tableau::new(&mut self.inner.ids, bewerb.id(), &seeding);
+        self.bewerbs
+            .get_mut(bewerb_id)
+            .unwrap()
+            .set_tableau(tableau);
+
+        Ok(())
+    }
+
+    /// End of the last pool of the bewerb plus the time for results
+    /// processing, `None` if no pool is scheduled.
+    fn tableau_earliest_start(&self, bewerb_id: u32) -> Option<NaiveDateTime> {
+        let gap = TimeDelta::minutes(self.inner.round_gap_minutes as i64);
+        self.get_placements()
+            .iter()
+            .filter(|x| x.group.bewerb_id == bewerb_id)
+            .map(|x| x.slot.end + gap)
+            .max()
+    }
+
+    /// The earliest run of free slots on an allowed piste from `earliest`
+    /// on while both fencers are available and not busy elsewhere.
+    fn find_bout_run(&self, bout: &Bout, earliest: NaiveDateTime) -> Option<Vec<Slot>> {
+        let id = bout.id();
+        let bewerb = self.bewerbs.get(id.bewerb_id)?;
+        let minutes = bewerb.weapon.de_bout_minutes();
+        let fencers = bout.get_fencers();
+
+        let groups = self
+            .get_placements()
+            .into_iter()
+            .filter(|x| x.fencers.iter().any(|f| fencers.contains(f)))
+            .map(|x| x.slot);
+        let bouts = self
+            .get_bout_placements()
+            .into_iter()
+            .filter(|x| x.bout != id && x.winner.is_none())
+            .filter(|x| x.fencers.iter().any(|f| fencers.contains(f)))
+            .map(|x| x.slot);
+        let busy: Vec<Slot> = groups.chain(bouts).collect();
+
+        self.get_slots()
+            .into_iter()
+            .filter(|x| x.start >= earliest && bewerb.allows_piste(x.piste))
+            .filter_map(|x| self.get_slot_run(&x.arena, minutes))
+            .find(|run| {
+                let Some(span) = Slot::span(run) else {
+                    return false;
+                };
+
+                run.iter().all(|x| {
+                    self.get_arena(&x.arena)
+                        .is_some_and(|a| a.is_free() && a.allows_bout(&id))
+                }) && !busy.iter().any(|x| x.overlaps(&span))
+                    && self.get_unavailable_fencers(&fencers, &span).is_empty()
+            })
+    }
+
+    /// Puts every bout whose fencers are known into the earliest free arena
+    /// slots from `now` on, after the pools of its bewerb. Bouts of larger
+    /// tables go first. Returns the bouts placed, the ones without a free
+    /// slot are left for the next call.
+    pub fn dispatch_bouts(&mut self, now: NaiveDateTime) -> Vec<BoutPlacement> {
+        let mut bouts: Vec<Arc<Bout>> = self
+            .all_bouts()
+            .into_iter()
+            .filter(|x| x.is_ready() && x.get_arena().is_none())
+            .collect();
+        bouts.sort_by_key(|x| (Reverse(x.table), x.position));
+
+        let mut res = Vec::new();
+        for bout in bouts {
+            let earliest = self
+                .tableau_earliest_start(bout.id().bewerb_id)
+                .map_or(now, |x| x.max(now));
+            let Some(run) = self.find_bout_run(&bout, earliest) else {
+                continue;
+            };
+
+            for slot in run.iter() {
+                if let Some(arena) = self.get_arena(&slot.arena) {
+                    Bout::add_to_arenaslot(bout.clone(), arena);
+                }
+            }
+            if let Some(placement) = self.get_bout_placement(&bout) {
+                res.push(placement);
+            }
+        }
+
+        res
+    }
+
+    /// Fails unless both fencers are known and the bout is not decided.
+    pub fn start_bout(&mut self, id: &BoutId, time: NaiveDateTime) -> Result<(), Error> {
+        let Some(bout) = self.get_bout(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid bout_id {:?}", id)));
+        };
+        if !bout.is_ready() {
+            return Err(Error::InvalidInput(format!(
+                "bout {:?} is not ready to be fenced",
+                id
+            )));
+        }
+
+        let mut state = bout.get_state();
+        state.actual_start = Some(time);
+        bout.set_state(state);
+        Ok(())
+    }
+
+    /// Updates the running score, fails unless both fencers are known and
+    /// the bout is not decided.
+    pub fn set_bout_score(&mut self, id: &BoutId, score: [u32; 2]) -> Result<(), Error> {
+        let Some(bout) = self.get_bout(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid bout_id {:?}", id)));
+        };
+        if !bout.is_ready() {
+            return Err(Error::InvalidInput(format!(
+                "bout {:?} is not ready to be fenced",
+                id
+            )));
+        }
+
+        let mut state = bout.get_state();
+        state.score = score;
+        bout.set_state(state);
+        Ok(())
+    }
+
+    /// Records the result, moves the winner on and dispatches the bouts that
+    /// became ready. A result can be corrected until the next bout of the
+    /// winner has started or is decided.
+    pub fn set_bout_winner(
+        &mut self,
+        id: &BoutId,
+        winner: u32,
+        time: NaiveDateTime,
+    ) -> Result<Vec<BoutPlacement>, Error> {
+        let Some(bout) = self.get_bout(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid bout_id {:?}", id)));
+        };
+        if !bout.is_ready() && bout.get_state().winner.is_none() {
+            return Err(Error::InvalidInput(format!(
+                "fencers of bout {:?} are not known yet",
+                id
+            )));
+        }
+        if !bout.get_fencers().contains(&winner) {
+            return Err(Error::InvalidInput(format!(
+                "Ivalid fencer_id {:?}",
+                winner
+            )));
+        }
+
+        let tableau = self.bewerbs.get(id.bewerb_id).unwrap().get_tableau();
+        if let Some(next) = tableau.next_bout(&bout) {
+            let state = next.get_state();
+            if state.actual_start.is_some() || state.winner.is_some() {
+                return Err(Error::InvalidInput(format!(
+                    "next bout {:?} has already started",
+                    next.id()
+                )));
+            }
+        }
+
+        tableau.set_winner(&bout, winner);
+        let mut state = bout.get_state();
+        state.actual_end = Some(time);
+        bout.set_state(state);
+
+        Ok(self.dispatch_bouts(time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{add_bewerb, date, fencers, groups, tournament};
+
+    /// A tableau of four fencers, its bouts ordered T4 first.
+    fn tableau() -> (Tournament, Vec<Arc<Bout>>) {
+        let mut t = tournament(8, 2);
+        let bewerb_id = add_bewerb(&mut t, "Foil", 1, 1, 4);
+        let seeding = fencers(&t, &groups(&t, bewerb_id)[0]);
+        t.create_tableau(bewerb_id, seeding).unwrap();
+
+        let bouts = t
+            .bewerbs
+            .get(bewerb_id)
+            .unwrap()
+            .get_tableau()
+            .bouts()
+            .to_vec();
+        (t, bouts)
+    }
+
+    fn time(hour: u32) -> NaiveDateTime {
+        date().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn winner_moves_on_and_can_be_corrected() {
+        let (mut t, bouts) = tableau();
+        let [first, second] = bouts[0].get_state().fencers.map(Option::unwrap);
+
+        t.set_bout_winner(&bouts[0].id(), first, time(10)).unwrap();
+        assert_eq!(bouts[2].get_state().fencers[0], Some(first));
+
+        t.set_bout_winner(&bouts[0].id(), second, time(10)).unwrap();
+        assert_eq!(bouts[0].get_state().winner, Some(second));
+        assert_eq!(bouts[2].get_state().fencers[0], Some(second));
+    }
+
+    #[test]
+    fn winner_has_to_be_in_the_bout() {
+        let (mut t, bouts) = tableau();
+        let other = bouts[1].get_fencers()[0];
+
+        assert!(t.set_bout_winner(&bouts[0].id(), other, time(10)).is_err());
+        assert_eq!(bouts[0].get_state().winner, None);
+    }
+
+    #[test]
+    fn bout_needs_both_fencers() {
+        let (mut t, bouts) = tableau();
+        let fencer = bouts[0].get_fencers()[0];
+        t.set_bout_winner(&bouts[0].id(), fencer, time(10)).unwrap();
+
+        assert!(t.set_bout_winner(&bouts[2].id(), fencer, time(10)).is_err());
+        assert!(t.start_bout(&bouts[2].id(), time(10)).is_err());
+    }
+
+    #[test]
+    fn no_correction_once_the_next_bout_started() {
+        let (mut t, bouts) = tableau();
+        let [first, second] = bouts[0].get_state().fencers.map(Option::unwrap);
+        let third = bouts[1].get_fencers()[0];
+        t.set_bout_winner(&bouts[0].id(), first, time(10)).unwrap();
+        t.set_bout_winner(&bouts[1].id(), third, time(10)).unwrap();
+        t.start_bout(&bouts[2].id(), time(11)).unwrap();
+
+        assert!(t.set_bout_winner(&bouts[0].id(), second, time(11)).is_err());
+        assert_eq!(bouts[0].get_state().winner, Some(first));
+        assert_eq!(bouts[2].get_state().fencers, [Some(first), Some(third)]);
+    }
+}
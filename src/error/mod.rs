@@ -1,10 +1,14 @@
 use std::io;
 
+use crate::integrity::Inconsistency;
+
 #[derive(Debug)]
 pub enum Error {
     InvalidInput(String),
     Io(io::Error),
     SerdeJson(serde_json::Error),
+    /// links of the tournament broken by a change, see `Tournament::verify`
+    Inconsistent(Vec<Inconsistency>),
 }
 
 impl From<io::Error> for Error {
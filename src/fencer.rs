@@ -4,16 +4,18 @@ use crate::error::Error;
 use crate::group::{Group, GroupId};
 use crate::tournament::SimpleFencer;
 
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::slice::{Iter, IterMut};
 use std::sync::Arc;
 use std::sync::Mutex;
 
+/// The groups a fencer is assigned to in one bewerb, at most one per round.
 #[derive(Clone, Debug, Default)]
 pub struct BewerbGroup {
     pub bewerb_id: BewerbId,
-    pub groups: Vec<Option<Arc<Group>>>,
+    pub groups: Vec<Arc<Group>>,
 }
 
 impl BewerbGroup {
@@ -33,11 +35,7 @@ pub struct BewerbGroupSaveable {
 
 impl From<&BewerbGroup> for BewerbGroupSaveable {
     fn from(bewerb: &BewerbGroup) -> Self {
-        let groups = bewerb
-            .groups
-            .iter()
-            .map(|x| x.as_ref().map(|x| x.id()))
-            .collect();
+        let groups = bewerb.groups.iter().map(|x| Some(x.id())).collect();
         Self {
             bewerb_id: bewerb.bewerb_id.clone(),
             groups,
@@ -45,10 +43,33 @@ impl From<&BewerbGroup> for BewerbGroupSaveable {
     }
 }
 
+/// A time the fencer can fence in.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AvailabilityWindow {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl AvailabilityWindow {
+    /// Fails if a window does not end after it starts.
+    pub fn check_all(windows: &[AvailabilityWindow]) -> Result<(), Error> {
+        match windows.iter().find(|x| x.end <= x.start) {
+            Some(window) => Err(Error::InvalidInput(format!(
+                "Ivalid availability window {:?}",
+                window
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Fencer {
     id: u32,
     name: Mutex<String>,
+    club: Mutex<Option<String>>,
+    /// the fencer is only available in these, always if empty
+    availability: Mutex<Vec<AvailabilityWindow>>,
     bewerbs: Mutex<Vec<BewerbGroup>>,
 }
 
@@ -56,6 +77,10 @@ pub struct Fencer {
 pub struct FencerSaveable {
     id: u32,
     name: String,
+    #[serde(default)]
+    club: Option<String>,
+    #[serde(default)]
+    availability: Vec<AvailabilityWindow>,
     bewerbs: Vec<BewerbGroupSaveable>,
 }
 
@@ -64,6 +89,8 @@ impl From<&Fencer> for FencerSaveable {
         Self {
             id: fencer.id,
             name: fencer.name.lock().unwrap().to_owned(),
+            club: fencer.get_club(),
+            availability: fencer.get_availability(),
             bewerbs: fencer
                 .bewerbs
                 .lock()
@@ -95,33 +122,22 @@ impl Fencer {
     pub fn from_saveable(fs: FencerSaveable, bewerbs: &UidContainer<Bewerb>) -> Arc<Fencer> {
         let mut bewerb_groups = Vec::new();
         for bewerb_group in fs.bewerbs.iter() {
-            let Some(bewerb) = bewerbs
-                .iter()
-                .find(|x| x.get_id() == bewerb_group.bewerb_id.bewerb_id)
-            else {
+            if bewerbs.get(bewerb_group.bewerb_id.bewerb_id).is_none() {
                 println!(
                     "Warning: could not find bewerb {:?}",
                     bewerb_group.bewerb_id
                 );
                 continue;
-            };
-
-            let mut groups = Vec::new();
-            for _i in 0..bewerb.n_rounds {
-                groups.push(None);
             }
 
-            let new_bewerb_group = BewerbGroup {
-                bewerb_id: bewerb_group.bewerb_id.clone(),
-                groups,
-            };
-
-            bewerb_groups.push(new_bewerb_group);
+            bewerb_groups.push(BewerbGroup::new(&bewerb_group.bewerb_id));
         }
 
         let res = Arc::new(Self {
             id: fs.id,
             name: Mutex::new(fs.name),
+            club: Mutex::new(fs.club),
+            availability: Mutex::new(fs.availability),
             bewerbs: Mutex::new(bewerb_groups),
         });
 
@@ -132,8 +148,7 @@ impl Fencer {
             .filter_map(|x| x.clone());
         for group_id in group_ids {
             let Some(group) = bewerbs
-                .iter()
-                .find(|x| x.get_id() == group_id.bewerb_id)
+                .get(group_id.bewerb_id)
                 .and_then(|x| x.get_group_by_id(&group_id))
             else {
                 println!("Cant find group \"{:?}\"", group_id);
@@ -146,27 +161,108 @@ impl Fencer {
         res
     }
 
-    pub fn new(name: String, bewerbs: Vec<BewerbId>) -> Self {
+    pub fn new(id: u32, name: String, bewerbs: Vec<BewerbId>) -> Self {
         Self {
-            id: 0,
+            id,
             name: Mutex::new(name),
+            club: Mutex::new(None),
+            availability: Mutex::new(Vec::new()),
             bewerbs: Mutex::new(bewerbs.iter().map(BewerbGroup::new).collect()),
         }
     }
 
+    /// Takes name and bewerbs from `sf`. The groups of bewerbs that are
+    /// still listed are kept, the fencer is taken out of the others.
     pub fn update(&self, sf: SimpleFencer) {
         *self.name.lock().unwrap() = sf.name;
-        *self.bewerbs.lock().unwrap() = sf
-            .bewerbs
-            .iter()
-            .map(|x| BewerbGroup::new(&x.into()))
-            .collect();
+
+        let mut bewerbs = self.bewerbs.lock().unwrap();
+        let mut removed = std::mem::take(&mut *bewerbs);
+        for id in sf.bewerbs.iter().map(BewerbId::from) {
+            match removed
+                .iter()
+                .position(|x| x.bewerb_id.bewerb_id == id.bewerb_id)
+            {
+                Some(pos) => bewerbs.push(removed.remove(pos)),
+                None => bewerbs.push(BewerbGroup::new(&id)),
+            }
+        }
+        drop(bewerbs);
+
+        for group in removed.iter().flat_map(|x| x.groups.iter()) {
+            group.remove_fencer(self.id);
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.lock().unwrap().clone()
+    }
+
+    pub fn get_club(&self) -> Option<String> {
+        self.club.lock().unwrap().clone()
+    }
+
+    pub fn set_club(&self, club: Option<String>) {
+        *self.club.lock().unwrap() = club;
+    }
+
+    pub fn get_availability(&self) -> Vec<AvailabilityWindow> {
+        self.availability.lock().unwrap().clone()
+    }
+
+    pub fn set_availability(&self, windows: Vec<AvailabilityWindow>) {
+        *self.availability.lock().unwrap() = windows;
+    }
+
+    /// Whether one window covers the whole time from `start` to `end`.
+    pub fn is_available(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        let windows = self.availability.lock().unwrap();
+        windows.is_empty() || windows.iter().any(|x| x.start <= start && end <= x.end)
     }
 
     pub fn is_same(&self, sf: &SimpleFencer) -> bool {
         self.id == sf.id && *self.name.lock().unwrap() == sf.name
     }
 
+    pub fn is_in_bewerb(&self, bewerb_id: u32) -> bool {
+        self.bewerbs
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|x| x.bewerb_id.bewerb_id == bewerb_id)
+    }
+
+    pub fn remove_bewerb(&self, bewerb_id: u32) {
+        self.bewerbs
+            .lock()
+            .unwrap()
+            .retain(|x| x.bewerb_id.bewerb_id != bewerb_id);
+    }
+
+    pub fn get_groups(&self) -> Vec<Arc<Group>> {
+        self.bewerbs
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|x| x.groups.iter().cloned())
+            .collect()
+    }
+
+    pub fn remove_group(&self, group: &Arc<Group>) {
+        for bewerb in self.bewerbs.lock().unwrap().iter_mut() {
+            bewerb.groups.retain(|x| !Arc::ptr_eq(x, group));
+        }
+    }
+
+    pub fn get_bewerb_ids(&self) -> Vec<u32> {
+        self.bewerbs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|x| x.bewerb_id.bewerb_id)
+            .collect()
+    }
+
     pub fn add_group(&self, group: Arc<Group>) {
         let mut locked = self.bewerbs.lock().unwrap();
         let Some(bewerb) = locked
@@ -177,19 +273,16 @@ impl Fencer {
             return;
         };
 
-        let Some(slot) = bewerb.groups.get_mut(group.id().round_id as usize) else {
-            println!(
-                "Error: bewerb \"{:?}\" has not enough slots \"{:?}\"",
-                bewerb.bewerb_id,
-                group.id()
-            );
-            return;
-        };
-
-        if let Some(slot) = slot {
-            slot.remove_fencer(self.id);
+        let round_id = group.id().round_id;
+        if let Some(pos) = bewerb
+            .groups
+            .iter()
+            .position(|x| x.id().round_id == round_id)
+        {
+            let old = bewerb.groups.remove(pos);
+            old.remove_fencer(self.id);
         }
-        *slot = Some(group);
+        bewerb.groups.push(group);
     }
 }
 
@@ -197,48 +290,34 @@ impl HasId for Fencer {
     fn get_id(&self) -> u32 {
         self.id
     }
-    fn set_id(&mut self, id: u32) {
-        self.id = id;
-    }
 }
 
 #[derive(Default, Debug)]
 pub struct Fencers {
-    fencers: Vec<Arc<Fencer>>,
+    fencers: UidContainer<Arc<Fencer>>,
 }
 
 impl Fencers {
-    fn get_new_id(&self) -> u32 {
-        let mut id = 0;
-        let ids: Vec<u32> = self.fencers.iter().map(|x| x.get_id()).collect();
-        while ids.contains(&id) {
-            id += 1;
-        }
-        id
-    }
-
-    pub fn push(&mut self, mut item: Fencer) {
-        let id = self.get_new_id();
-        item.set_id(id);
-        self.fencers.push(Arc::new(item));
+    pub fn insert(&mut self, item: Fencer) {
+        self.fencers.insert(Arc::new(item));
     }
 
     pub fn remove(&mut self, id: u32) {
-        self.fencers.retain(|x| x.get_id() != id);
+        self.fencers.remove(id);
     }
 
     pub fn get(&self, id: u32) -> Option<Arc<Fencer>> {
-        self.fencers.iter().find(|x| x.get_id() == id).cloned()
+        self.fencers.get(id).cloned()
     }
 
     pub fn from(fencers: Vec<FencerSaveable>, bewerbs: &UidContainer<Bewerb>) -> Self {
-        let mut vec = Vec::new();
+        let mut res = UidContainer::default();
         for fencer in fencers {
             let item = Fencer::from_saveable(fencer, bewerbs);
-            vec.push(item);
+            res.insert(item);
         }
 
-        Self { fencers: vec }
+        Self { fencers: res }
     }
 
     pub fn iter(&self) -> Iter<'_, Arc<Fencer>> {
@@ -271,10 +350,45 @@ impl Fencers {
 impl From<&Fencers> for Vec<FencerSaveable> {
     fn from(fencers: &Fencers) -> Vec<FencerSaveable> {
         let mut vec = Vec::new();
-        for fencer in &fencers.fencers {
+        for fencer in fencers.iter() {
             let fs = fencer.as_ref().into();
             vec.push(fs);
         }
         vec
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use crate::tournament::BewerbIdentifier;
+
+    #[test]
+    fn update_keeps_groups_of_listed_bewerbs() {
+        let mut tournament = test_support::tournament(4, 2);
+        let foil = test_support::add_bewerb(&mut tournament, "Foil", 1, 1, 4);
+        let epee = test_support::add_bewerb(&mut tournament, "Epee", 1, 1, 0);
+        let fencer = tournament.fencers.iter().next().unwrap().clone();
+
+        let bewerb = |id: u32| {
+            let bewerb = tournament.bewerbs.get(id).unwrap();
+            BewerbIdentifier::from(bewerb.id())
+        };
+        fencer.update(SimpleFencer {
+            id: fencer.get_id(),
+            name: fencer.name(),
+            bewerbs: vec![bewerb(foil), bewerb(epee)],
+        });
+        assert_eq!(fencer.get_groups().len(), 1);
+        assert!(tournament.verify().is_empty());
+
+        fencer.update(SimpleFencer {
+            id: fencer.get_id(),
+            name: fencer.name(),
+            bewerbs: vec![bewerb(epee)],
+        });
+        assert!(fencer.get_groups().is_empty());
+        assert!(tournament.verify().is_empty());
+    }
+}
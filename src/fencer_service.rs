@@ -1,19 +1,18 @@
+use crate::store::TournamentStore;
 use crate::tournament::fencer_server;
 use crate::tournament::{
     GetAllFencersRequest, GetAllFencersResponse, UpdateFencersRequest, UpdateFencersResponse,
 };
-use crate::tournament_core::Tournament;
 
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 #[derive(Debug)]
 pub struct FencerService {
-    tournament: Arc<Mutex<Option<Tournament>>>,
+    tournament: Arc<TournamentStore>,
 }
 
 impl FencerService {
-    pub fn new(tournament: Arc<Mutex<Option<Tournament>>>) -> Self {
+    pub fn new(tournament: Arc<TournamentStore>) -> Self {
         Self { tournament }
     }
 }
@@ -28,7 +27,7 @@ impl fencer_server::Fencer for FencerService {
         &self,
         _request: tonic::Request<GetAllFencersRequest>,
     ) -> std::result::Result<tonic::Response<GetAllFencersResponse>, tonic::Status> {
-        let Some(ref mut tournament) = *self.tournament.lock().await else {
+        let Some(tournament) = self.tournament.snapshot() else {
             return Err(not_loaded());
         };
 
@@ -40,11 +39,18 @@ impl fencer_server::Fencer for FencerService {
         &self,
         request: tonic::Request<UpdateFencersRequest>,
     ) -> std::result::Result<tonic::Response<UpdateFencersResponse>, tonic::Status> {
-        let Some(ref mut tournament) = *self.tournament.lock().await else {
+        let fencers = request.into_inner().fencers;
+        let Some(res) = self
+            .tournament
+            .write(|tournament| {
+                tournament.update_fencers(fencers);
+                Ok(())
+            })
+            .await
+        else {
             return Err(not_loaded());
         };
-
-        tournament.update_fencers(request.into_inner().fencers);
+        res?;
 
         Ok(tonic::Response::new(UpdateFencersResponse {}))
     }
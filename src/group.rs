@@ -1,12 +1,14 @@
 use crate::container::HasId;
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
 use crate::arena_slot::ArenaSlot;
+use crate::bewerb::Weapon;
 use crate::fencer::Fencer;
 use crate::tournament::GroupIdentifier;
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct GroupId {
     pub bewerb_name: String,
     pub bewerb_id: u32,
@@ -36,22 +38,46 @@ impl From<GroupIdentifier> for GroupId {
     }
 }
 
+/// What actually happened to a group compared to its arena slots.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct GroupProgress {
+    pub actual_start: Option<NaiveDateTime>,
+    pub actual_end: Option<NaiveDateTime>,
+    /// reported lateness on top of the planned or actual start
+    pub delay_minutes: u32,
+    /// when the group was called to its piste, later entries are re-calls
+    #[serde(default)]
+    pub calls: Vec<NaiveDateTime>,
+    /// fencers that did not show up after being called
+    #[serde(default)]
+    pub no_shows: Vec<u32>,
+}
+
 #[derive(Debug, Default)]
 pub struct Group {
     id: Mutex<GroupId>,
-    arena_slot: Mutex<Option<Arc<ArenaSlot>>>,
+    /// consecutive arena slots of one piste, in chronological order
+    arena_slots: Mutex<Vec<Arc<ArenaSlot>>>,
     fencers: Mutex<Vec<Arc<Fencer>>>,
+    referees: Mutex<Vec<String>>,
+    progress: Mutex<GroupProgress>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct GroupSaveable {
     id: GroupId,
+    #[serde(default)]
+    referees: Vec<String>,
+    #[serde(default)]
+    progress: GroupProgress,
 }
 
 impl From<&Group> for GroupSaveable {
     fn from(group: &Group) -> Self {
         Self {
             id: group.id.lock().unwrap().clone(),
+            referees: group.get_referees(),
+            progress: group.get_progress(),
         }
     }
 }
@@ -60,8 +86,10 @@ impl Group {
     pub fn from_saveable(group: &GroupSaveable) -> Self {
         Self {
             id: Mutex::new(group.id.clone()),
-            arena_slot: Mutex::new(None),
+            arena_slots: Mutex::new(Vec::new()),
             fencers: Mutex::new(Vec::new()),
+            referees: Mutex::new(group.referees.clone()),
+            progress: Mutex::new(group.progress.clone()),
         }
     }
 
@@ -70,8 +98,7 @@ impl Group {
 
         Self {
             id,
-            arena_slot: Mutex::new(None),
-            fencers: Mutex::new(Vec::new()),
+            ..Default::default()
         }
     }
 
@@ -79,21 +106,65 @@ impl Group {
         self.id.lock().unwrap().clone()
     }
 
+    /// The arena slot the group starts in.
     pub fn get_arena(&self) -> Option<Arc<ArenaSlot>> {
-        self.arena_slot.lock().unwrap().as_ref().cloned()
+        self.arena_slots.lock().unwrap().first().cloned()
+    }
+
+    pub fn get_arenas(&self) -> Vec<Arc<ArenaSlot>> {
+        self.arena_slots.lock().unwrap().clone()
     }
 
     pub fn set_arena(&self, slot: Option<Arc<ArenaSlot>>) {
-        *self.arena_slot.lock().unwrap() = slot;
+        *self.arena_slots.lock().unwrap() = slot.into_iter().collect();
+    }
+
+    pub fn set_arenas(&self, slots: Vec<Arc<ArenaSlot>>) {
+        *self.arena_slots.lock().unwrap() = slots;
+    }
+
+    pub fn add_arena(&self, slot: Arc<ArenaSlot>) {
+        self.arena_slots.lock().unwrap().push(slot);
+    }
+
+    pub fn remove_arena(&self, slot: &Arc<ArenaSlot>) {
+        self.arena_slots
+            .lock()
+            .unwrap()
+            .retain(|x| !Arc::ptr_eq(x, slot));
     }
 
     pub fn add_to_arenaslot(group: Arc<Group>, arena: Arc<ArenaSlot>) {
         if let Some(old_group) = arena.get_group() {
-            old_group.set_arena(None);
+            old_group.remove_arena(&arena);
+        }
+        if let Some(old_bout) = arena.get_bout() {
+            old_bout.remove_arena(&arena);
         }
 
         arena.set_group(Some(group.clone()));
-        group.set_arena(Some(arena));
+        group.add_arena(arena);
+    }
+
+    pub fn get_referees(&self) -> Vec<String> {
+        self.referees.lock().unwrap().clone()
+    }
+
+    pub fn set_referees(&self, referees: Vec<String>) {
+        *self.referees.lock().unwrap() = referees;
+    }
+
+    pub fn get_progress(&self) -> GroupProgress {
+        self.progress.lock().unwrap().clone()
+    }
+
+    pub fn set_progress(&self, progress: GroupProgress) {
+        *self.progress.lock().unwrap() = progress;
+    }
+
+    /// Estimated time to fence all bouts of the group.
+    pub fn estimated_minutes(&self, weapon: Weapon) -> u32 {
+        weapon.pool_minutes(self.fencers.lock().unwrap().len() as u32)
     }
 
     pub fn add_fencer(&self, fencer: Arc<Fencer>) {
@@ -104,7 +175,11 @@ impl Group {
         self.fencers
             .lock()
             .unwrap()
-            .retain(|x| x.get_id() == fencer_id);
+            .retain(|x| x.get_id() != fencer_id);
+    }
+
+    pub fn get_fencers(&self) -> Vec<Arc<Fencer>> {
+        self.fencers.lock().unwrap().clone()
     }
 
     pub fn get_fencer(&self, fencer: &Arc<Fencer>) -> Option<Arc<Fencer>> {
@@ -130,7 +205,4 @@ impl HasId for Group {
     fn get_id(&self) -> u32 {
         self.id.lock().unwrap().group_id
     }
-    fn set_id(&mut self, id: u32) {
-        self.id.lock().unwrap().group_id = id;
-    }
 }
@@ -0,0 +1,375 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::arena_slot::ArenaSlotId;
+use crate::container::HasId;
+use crate::error::Error;
+use crate::group::GroupId;
+use crate::tableau::BoutId;
+use crate::tournament_core::Tournament;
+
+/// A broken or one-sided link between two entities of the object graph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Inconsistency {
+    /// The group points at an arena slot that holds another group or none.
+    GroupArenaMismatch { group: GroupId, arena: ArenaSlotId },
+    /// The arena slot holds a group that points at another arena slot or none.
+    ArenaGroupMismatch { arena: ArenaSlotId, group: GroupId },
+    /// The group points at an arena slot that is not part of any day.
+    UnknownArena { group: GroupId, arena: ArenaSlotId },
+    /// The arena slot holds a group that is not part of any bewerb.
+    UnknownGroup { arena: ArenaSlotId, group: GroupId },
+    /// The fencer is assigned to a group that is not part of any bewerb.
+    FencerUnknownGroup { fencer: u32, group: GroupId },
+    /// The fencer is registered for a bewerb that does not exist.
+    FencerUnknownBewerb { fencer: u32, bewerb_id: u32 },
+    /// The fencer is assigned to the group but the group does not list them.
+    FencerNotInGroup { fencer: u32, group: GroupId },
+    /// The group lists a fencer that is not assigned to it.
+    GroupFencerNotLinked { group: GroupId, fencer: u32 },
+    /// The group lists a fencer that is not part of the tournament.
+    GroupUnknownFencer { group: GroupId, fencer: u32 },
+    /// The group lists the same fencer more than once.
+    DuplicateFencer { group: GroupId, fencer: u32 },
+    /// The bout points at an arena slot that does not hold it or is not
+    /// part of any day.
+    BoutArenaMismatch { bout: BoutId, arena: ArenaSlotId },
+    /// The arena slot holds a bout that does not point back at it or is
+    /// not part of any tableau.
+    ArenaBoutMismatch { arena: ArenaSlotId, bout: BoutId },
+}
+
+fn verify_after_mutation_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var_os("TOURNAMENT_VERIFY").is_some())
+}
+
+impl Tournament {
+    /// Walks every two-way link of the tournament and reports the ones
+    /// that do not match up.
+    pub fn verify(&self) -> Vec<Inconsistency> {
+        let groups = self.all_groups();
+        let arenas = self.all_arenas();
+        let mut res = Vec::new();
+
+        for group in groups.iter() {
+            for arena in group.get_arenas() {
+                if !arenas.iter().any(|x| Arc::ptr_eq(x, &arena)) {
+                    res.push(Inconsistency::UnknownArena {
+                        group: group.id(),
+                        arena: arena.id().clone(),
+                    });
+                } else if !arena.get_group().is_some_and(|x| Arc::ptr_eq(&x, group)) {
+                    res.push(Inconsistency::GroupArenaMismatch {
+                        group: group.id(),
+                        arena: arena.id().clone(),
+                    });
+                }
+            }
+
+            let mut seen: Vec<u32> = Vec::new();
+            for fencer in group.get_fencers() {
+                let fencer_id = fencer.get_id();
+                if seen.contains(&fencer_id) {
+                    res.push(Inconsistency::DuplicateFencer {
+                        group: group.id(),
+                        fencer: fencer_id,
+                    });
+                    continue;
+                }
+                seen.push(fencer_id);
+
+                if !self.fencers.iter().any(|x| Arc::ptr_eq(x, &fencer)) {
+                    res.push(Inconsistency::GroupUnknownFencer {
+                        group: group.id(),
+                        fencer: fencer_id,
+                    });
+                } else if !fencer.get_groups().iter().any(|x| Arc::ptr_eq(x, group)) {
+                    res.push(Inconsistency::GroupFencerNotLinked {
+                        group: group.id(),
+                        fencer: fencer_id,
+                    });
+                }
+            }
+        }
+
+        for arena in arenas.iter() {
+            let Some(group) = arena.get_group() else {
+                continue;
+            };
+
+            if !groups.iter().any(|x| Arc::ptr_eq(x, &group)) {
+                res.push(Inconsistency::UnknownGroup {
+                    arena: arena.id().clone(),
+                    group: group.id(),
+                });
+            } else if !group.get_arenas().iter().any(|x| Arc::ptr_eq(x, arena)) {
+                res.push(Inconsistency::ArenaGroupMismatch {
+                    arena: arena.id().clone(),
+                    group: group.id(),
+                });
+            }
+        }
+
+        let bouts = self.all_bouts();
+        for bout in bouts.iter() {
+            for arena in bout.get_arenas() {
+                let known = arenas.iter().any(|x| Arc::ptr_eq(x, &arena));
+                if !known || !arena.get_bout().is_some_and(|x| Arc::ptr_eq(&x, bout)) {
+                    res.push(Inconsistency::BoutArenaMismatch {
+                        bout: bout.id(),
+                        arena: arena.id().clone(),
+                    });
+                }
+            }
+        }
+
+        for arena in arenas.iter() {
+            let Some(bout) = arena.get_bout() else {
+                continue;
+            };
+
+            let known = bouts.iter().any(|x| Arc::ptr_eq(x, &bout));
+            if !known || !bout.get_arenas().iter().any(|x| Arc::ptr_eq(x, arena)) {
+                res.push(Inconsistency::ArenaBoutMismatch {
+                    arena: arena.id().clone(),
+                    bout: bout.id(),
+                });
+            }
+        }
+
+        for fencer in self.fencers.iter() {
+            for bewerb_id in fencer.get_bewerb_ids() {
+                if self.bewerbs.get(bewerb_id).is_none() {
+                    res.push(Inconsistency::FencerUnknownBewerb {
+                        fencer: fencer.get_id(),
+                        bewerb_id,
+                    });
+                }
+            }
+
+            for group in fencer.get_groups() {
+                if !groups.iter().any(|x| Arc::ptr_eq(x, &group)) {
+                    res.push(Inconsistency::FencerUnknownGroup {
+                        fencer: fencer.get_id(),
+                        group: group.id(),
+                    });
+                } else if group.get_fencer(fencer).is_none() {
+                    res.push(Inconsistency::FencerNotInGroup {
+                        fencer: fencer.get_id(),
+                        group: group.id(),
+                    });
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Fixes every inconsistency found by `verify` and returns them.
+    ///
+    /// One-sided links are completed when the other side is free and
+    /// dropped otherwise, references to unknown entities are dropped.
+    /// Broken links of bouts are dropped, `dispatch_bouts` places the bouts
+    /// again.
+    pub fn repair(&mut self) -> Vec<Inconsistency> {
+        let res = self.verify();
+        let groups = self.all_groups();
+        let bouts = self.all_bouts();
+        let arenas = self.all_arenas();
+
+        for inconsistency in res.iter() {
+            match inconsistency {
+                Inconsistency::GroupArenaMismatch { group, arena }
+                | Inconsistency::UnknownArena { group, arena } => {
+                    let Some(group) = groups.iter().find(|x| x.id() == *group) else {
+                        continue;
+                    };
+                    let Some(arena) = group.get_arenas().into_iter().find(|x| x.id() == arena)
+                    else {
+                        continue;
+                    };
+
+                    if arena.is_free() && arenas.iter().any(|x| Arc::ptr_eq(x, &arena)) {
+                        arena.set_group(Some(group.clone()));
+                    } else {
+                        group.remove_arena(&arena);
+                    }
+                }
+                Inconsistency::ArenaGroupMismatch { arena, .. }
+                | Inconsistency::UnknownGroup { arena, .. } => {
+                    let Some(arena) = arenas.iter().find(|x| x.id() == arena) else {
+                        continue;
+                    };
+                    let Some(group) = arena.get_group() else {
+                        continue;
+                    };
+
+                    if group.get_arena().is_none() && groups.iter().any(|x| Arc::ptr_eq(x, &group))
+                    {
+                        group.add_arena(arena.clone());
+                    } else {
+                        arena.set_group(None);
+                    }
+                }
+                Inconsistency::BoutArenaMismatch { bout, arena } => {
+                    let Some(bout) = bouts.iter().find(|x| x.id() == *bout) else {
+                        continue;
+                    };
+                    let stale = bout.get_arenas().into_iter().find(|x| x.id() == arena);
+                    if let Some(stale) = stale {
+                        bout.remove_arena(&stale);
+                    }
+                }
+                Inconsistency::ArenaBoutMismatch { arena, .. } => {
+                    if let Some(arena) = arenas.iter().find(|x| x.id() == arena) {
+                        arena.set_bout(None);
+                    }
+                }
+                Inconsistency::FencerUnknownGroup { fencer, group } => {
+                    let Some(fencer) = self.fencers.get(*fencer) else {
+                        continue;
+                    };
+                    let stale = fencer.get_groups().into_iter().find(|x| x.id() == *group);
+                    if let Some(stale) = stale {
+                        fencer.remove_group(&stale);
+                    }
+                }
+                Inconsistency::FencerUnknownBewerb { fencer, bewerb_id } => {
+                    if let Some(fencer) = self.fencers.get(*fencer) {
+                        fencer.remove_bewerb(*bewerb_id);
+                    }
+                }
+                Inconsistency::FencerNotInGroup { fencer, group } => {
+                    let Some(fencer) = self.fencers.get(*fencer) else {
+                        continue;
+                    };
+                    if let Some(group) = groups.iter().find(|x| x.id() == *group) {
+                        group.add_fencer(fencer);
+                    }
+                }
+                Inconsistency::GroupFencerNotLinked { group, fencer } => {
+                    let Some(group) = groups.iter().find(|x| x.id() == *group) else {
+                        continue;
+                    };
+                    let Some(fencer) = self.fencers.get(*fencer) else {
+                        continue;
+                    };
+
+                    if fencer.is_in_bewerb(group.id().bewerb_id) {
+                        fencer.add_group(group.clone());
+                    } else {
+                        group.remove_fencer(fencer.get_id());
+                    }
+                }
+                Inconsistency::GroupUnknownFencer { group, fencer }
+                | Inconsistency::DuplicateFencer { group, fencer } => {
+                    let Some(group) = groups.iter().find(|x| x.id() == *group) else {
+                        continue;
+                    };
+
+                    group.remove_fencer(*fencer);
+                    if let Some(fencer) = self.fencers.get(*fencer) {
+                        if fencer.get_groups().iter().any(|x| Arc::ptr_eq(x, group)) {
+                            group.add_fencer(fencer);
+                        }
+                    }
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Runs `verify` after a mutation when `TOURNAMENT_VERIFY` is set and
+    /// fails if a link is broken. Does nothing in release builds.
+    pub(crate) fn verify_after_mutation(&self) -> Result<(), Error> {
+        if !cfg!(debug_assertions) || !verify_after_mutation_enabled() {
+            return Ok(());
+        }
+
+        let inconsistencies = self.verify();
+        if !inconsistencies.is_empty() {
+            return Err(Error::Inconsistent(inconsistencies));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn scheduled() -> (Tournament, GroupId) {
+        let mut tournament = test_support::tournament(4, 2);
+        let bewerb_id = test_support::add_bewerb(&mut tournament, "Foil", 1, 2, 3);
+        let group = test_support::groups(&tournament, bewerb_id)[0].clone();
+        let slot = test_support::slot(&tournament, 0, 0);
+        tournament.add_group_to_arena(&group, &slot).unwrap();
+        (tournament, group)
+    }
+
+    #[test]
+    fn consistent_tournament_verifies() {
+        let (tournament, _) = scheduled();
+        assert!(tournament.verify().is_empty());
+        assert!(tournament.deep_clone().verify().is_empty());
+    }
+
+    #[test]
+    fn arena_without_group_link_is_reported_and_repaired() {
+        let (mut tournament, group) = scheduled();
+        let arena = tournament.get_group(&group).unwrap().get_arenas()[0].clone();
+        arena.set_group(None);
+
+        let found = tournament.verify();
+        assert!(found.contains(&Inconsistency::GroupArenaMismatch {
+            group: group.clone(),
+            arena: arena.id().clone(),
+        }));
+
+        assert_eq!(tournament.repair(), found);
+        assert!(tournament.verify().is_empty());
+        assert!(arena.get_group().is_some_and(|x| x.id() == group));
+    }
+
+    #[test]
+    fn fencer_missing_in_group_is_reported_and_repaired() {
+        let (mut tournament, group) = scheduled();
+        let fencer = test_support::fencers(&tournament, &group)[0];
+        tournament.get_group(&group).unwrap().remove_fencer(fencer);
+
+        let found = tournament.verify();
+        assert_eq!(
+            found,
+            vec![Inconsistency::FencerNotInGroup {
+                fencer,
+                group: group.clone(),
+            }]
+        );
+
+        tournament.repair();
+        assert!(tournament.verify().is_empty());
+        assert!(test_support::fencers(&tournament, &group).contains(&fencer));
+    }
+
+    #[test]
+    fn duplicate_fencer_is_repaired() {
+        let (mut tournament, group) = scheduled();
+        let fencer_id = test_support::fencers(&tournament, &group)[0];
+        let fencer = tournament.fencers.get(fencer_id).unwrap();
+        tournament.get_group(&group).unwrap().add_fencer(fencer);
+
+        assert_eq!(
+            tournament.verify(),
+            vec![Inconsistency::DuplicateFencer {
+                group: group.clone(),
+                fencer: fencer_id,
+            }]
+        );
+
+        tournament.repair();
+        assert!(tournament.verify().is_empty());
+        assert_eq!(test_support::fencers(&tournament, &group).len(), 3);
+    }
+}
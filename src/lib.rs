@@ -1,12 +1,28 @@
+pub mod calls;
+pub mod capacity;
 mod container;
+pub mod display;
+pub mod elimination;
 mod error;
 pub mod fencer_service;
+pub mod integrity;
+pub mod live;
+pub mod optimizer;
+pub mod rearrange;
+pub mod report;
+mod schedule;
+mod scheduler;
+pub mod store;
+mod time_zone;
 pub mod tournament_core;
 pub mod tournament_service;
+mod validation;
+pub mod venue;
 
 mod bewerb;
 mod group;
 mod round;
+mod tableau;
 
 mod arena_slot;
 pub mod day;
@@ -14,6 +30,9 @@ mod timeslot;
 
 mod fencer;
 
+#[cfg(test)]
+mod test_support;
+
 pub mod tournament {
     tonic::include_proto!("tournament");
 }
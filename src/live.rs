@@ -0,0 +1,330 @@
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::arena_slot::ArenaSlotId;
+use crate::container::HasId;
+use crate::error::Error;
+use crate::group::{GroupId, GroupProgress};
+use crate::schedule::Placement;
+use crate::tournament_core::Tournament;
+
+/// Whose groups are running late.
+#[derive(Clone, Debug)]
+pub enum DelayTarget {
+    Piste { day_id: u32, piste: u32 },
+    Bewerb(u32),
+}
+
+/// A group `reflow` moved, `to` is `None` if no later slot was free and the
+/// group stays where it is although it can not start in time.
+#[derive(Clone, Debug)]
+pub struct ScheduleChange {
+    pub group: GroupId,
+    pub from: ArenaSlotId,
+    pub to: Option<ArenaSlotId>,
+}
+
+impl Tournament {
+    pub(crate) fn get_group_progress(&self, id: &GroupId) -> GroupProgress {
+        self.get_group(id)
+            .map(|x| x.get_progress())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn update_progress(
+        &mut self,
+        id: &GroupId,
+        f: impl FnOnce(&mut GroupProgress),
+    ) -> Result<(), Error> {
+        let Some(group) = self.get_group(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid group_id {:?}", id)));
+        };
+
+        let mut progress = group.get_progress();
+        f(&mut progress);
+        group.set_progress(progress);
+        Ok(())
+    }
+
+    pub fn start_group(&mut self, id: &GroupId, time: NaiveDateTime) -> Result<(), Error> {
+        self.update_progress(id, |x| {
+            x.actual_start = Some(time);
+            x.actual_end = None;
+        })
+    }
+
+    pub fn finish_group(&mut self, id: &GroupId, time: NaiveDateTime) -> Result<(), Error> {
+        self.update_progress(id, |x| x.actual_end = Some(time))
+    }
+
+    /// Actual start if the group has started, planned start otherwise.
+    pub fn expected_start(&self, placement: &Placement) -> NaiveDateTime {
+        let progress = self.get_group_progress(&placement.group);
+        progress.actual_start.unwrap_or(placement.slot.start)
+    }
+
+    /// Actual end if the group has finished, otherwise the planned end moved
+    /// by a late start and the reported delay.
+    pub fn expected_end(&self, placement: &Placement) -> NaiveDateTime {
+        let progress = self.get_group_progress(&placement.group);
+        if let Some(end) = progress.actual_end {
+            return end;
+        }
+
+        let late_start = progress
+            .actual_start
+            .map(|x| x - placement.slot.start)
+            .unwrap_or_default();
+        placement.slot.end + late_start + TimeDelta::minutes(progress.delay_minutes as i64)
+    }
+
+    /// Earliest time the group of `current` can start when the groups and
+    /// elimination bouts before it on its piste, of its fencers and of the
+    /// previous round end as expected.
+    fn earliest_start(&self, current: &Placement, placements: &[Placement]) -> NaiveDateTime {
+        let gap = TimeDelta::minutes(self.inner.round_gap_minutes as i64);
+        let previous_round = self.bewerbs.get(current.group.bewerb_id).and_then(|x| {
+            let round_ids = x.get_round_ids();
+            let pos = round_ids
+                .iter()
+                .position(|x| *x == current.group.round_id)?;
+            pos.checked_sub(1).map(|x| round_ids[x])
+        });
+
+        let mut res = NaiveDateTime::MIN;
+        for other in placements.iter().filter(|x| x.group != current.group) {
+            let end = self.expected_end(other);
+
+            let is_previous_round = other.group.bewerb_id == current.group.bewerb_id
+                && Some(other.group.round_id) == previous_round;
+            if is_previous_round {
+                res = res.max(end + gap);
+                continue;
+            }
+
+            let before = other.slot.start < current.slot.start
+                || self.get_group_progress(&other.group).actual_start.is_some();
+            if !before {
+                continue;
+            }
+
+            let same_piste = other.slot.arena.day_id == current.slot.arena.day_id
+                && other.slot.piste == current.slot.piste;
+            let shares_fencer = other.fencers.iter().any(|x| current.fencers.contains(x));
+            if same_piste || shares_fencer {
+                res = res.max(end);
+            }
+        }
+
+        for bout in self.get_bout_placements() {
+            let started = self
+                .get_bout(&bout.bout)
+                .is_some_and(|x| x.get_state().actual_start.is_some());
+            if bout.slot.start >= current.slot.start && !started {
+                continue;
+            }
+
+            let same_piste = bout.slot.arena.day_id == current.slot.arena.day_id
+                && bout.slot.piste == current.slot.piste;
+            let shares_fencer = bout.fencers.iter().any(|x| current.fencers.contains(x));
+            if same_piste || shares_fencer {
+                res = res.max(self.expected_bout_end(&bout));
+            }
+        }
+        res
+    }
+
+    /// The earliest free arena slot at or after `earliest` the group may
+    /// use, its own piste wins between slots starting at the same time.
+    fn find_reflow_slot(
+        &self,
+        group: &GroupId,
+        piste: u32,
+        earliest: NaiveDateTime,
+    ) -> Option<ArenaSlotId> {
+        let bewerb = self.bewerbs.get(group.bewerb_id)?;
+        let required = bewerb.get_required_attributes(group.round_id);
+        let free = self.get_free_arenas_for(group);
+        let minutes = self.get_group_minutes(group);
+        let fencers: Vec<u32> = self
+            .get_group(group)
+            .map(|x| x.get_fencers().iter().map(|f| f.get_id()).collect())
+            .unwrap_or_default();
+
+        self.get_slots()
+            .into_iter()
+            .filter(|x| x.start >= earliest && free.contains(&x.arena))
+            .filter(|x| bewerb.allows_piste(x.piste))
+            .filter(|x| self.piste_has_attributes(x.piste, &required))
+            .filter(|x| {
+                self.get_slot_run(&x.arena, minutes)
+                    .and_then(|run| Placement::new(group.clone(), &run, fencers.clone()))
+                    .is_some_and(|p| self.get_unavailable_fencers(&fencers, &p.slot).is_empty())
+            })
+            .min_by_key(|x| (x.start, x.piste != piste))
+            .map(|x| x.arena)
+    }
+
+    fn reflow_internal(&mut self) -> Result<Vec<ScheduleChange>, Error> {
+        let mut order: Vec<Placement> = self
+            .get_placements()
+            .into_iter()
+            .filter(|x| {
+                let progress = self.get_group_progress(&x.group);
+                progress.actual_start.is_none() && progress.actual_end.is_none()
+            })
+            .collect();
+        order.sort_by_key(|x| x.slot.start);
+
+        let mut res = Vec::new();
+        for planned in order {
+            let placements = self.get_placements();
+            let Some(current) = placements.iter().find(|x| x.group == planned.group) else {
+                continue;
+            };
+
+            let earliest = self.earliest_start(current, &placements);
+            if current.slot.start >= earliest {
+                continue;
+            }
+
+            let group_id = current.group.clone();
+            let from = current.slot.arena.clone();
+            let planned_start = current.slot.start;
+            let piste = current.slot.piste;
+            self.freeup_group(&group_id)?;
+
+            let to = self.find_reflow_slot(&group_id, piste, earliest);
+            match &to {
+                Some(to) => {
+                    self.add_group_to_arena(&group_id, to)?;
+                    // the move already makes up for the reported delay
+                    let shift = self
+                        .get_slot(to)
+                        .map_or(0, |x| (x.start - planned_start).num_minutes().max(0));
+                    self.update_progress(&group_id, |x| {
+                        x.delay_minutes = x.delay_minutes.saturating_sub(shift as u32)
+                    })?;
+                }
+                None => {
+                    self.add_group_to_arena(&group_id, &from)?;
+                }
+            }
+            res.push(ScheduleChange {
+                group: group_id,
+                from,
+                to,
+            });
+        }
+
+        Ok(res)
+    }
+
+    /// Moves every group that has not started and can not start at its
+    /// planned time anymore to the earliest fitting free slot, a group
+    /// without one stays where it is. Returns all moves, nothing is changed
+    /// if one of them fails.
+    pub fn reflow(&mut self) -> Result<Vec<ScheduleChange>, Error> {
+        self.transaction(|next| next.reflow_internal())
+    }
+
+    /// Marks the groups currently fenced on the piste or in the bewerb as
+    /// `minutes` late and re-flows the schedule. Without a running group
+    /// the next ones to start are late.
+    pub fn report_delay(
+        &mut self,
+        target: &DelayTarget,
+        minutes: u32,
+    ) -> Result<Vec<ScheduleChange>, Error> {
+        let unfinished: Vec<Placement> = self
+            .get_placements()
+            .into_iter()
+            .filter(|x| match target {
+                DelayTarget::Piste { day_id, piste } => {
+                    x.slot.arena.day_id == *day_id && x.slot.piste == *piste
+                }
+                DelayTarget::Bewerb(bewerb_id) => x.group.bewerb_id == *bewerb_id,
+            })
+            .filter(|x| self.get_group_progress(&x.group).actual_end.is_none())
+            .collect();
+
+        let running: Vec<&Placement> = unfinished
+            .iter()
+            .filter(|x| self.get_group_progress(&x.group).actual_start.is_some())
+            .collect();
+        let late = if running.is_empty() {
+            let next = unfinished.iter().map(|x| x.slot.start).min();
+            unfinished
+                .iter()
+                .filter(|x| Some(x.slot.start) == next)
+                .collect()
+        } else {
+            running
+        };
+
+        if late.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "no group left to delay for {:?}",
+                target
+            )));
+        }
+
+        self.transaction(|next| {
+            for placement in late {
+                next.update_progress(&placement.group, |x| x.delay_minutes += minutes)?;
+            }
+            next.reflow_internal()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{add_bewerb, date, groups, slot, tournament};
+
+    /// Two groups of one round one after the other on piste 0, the first
+    /// running an hour late.
+    fn late(n_ts: u32) -> (Tournament, Vec<GroupId>) {
+        let mut t = tournament(n_ts, 1);
+        let bewerb_id = add_bewerb(&mut t, "Foil", 1, 2, 4);
+        let groups = groups(&t, bewerb_id);
+        t.add_group_to_arena(&groups[0], &slot(&t, 0, 0)).unwrap();
+        t.add_group_to_arena(&groups[1], &slot(&t, 1, 0)).unwrap();
+
+        let start = date().and_hms_opt(9, 0, 0).unwrap();
+        t.start_group(&groups[0], start).unwrap();
+        t.update_progress(&groups[0], |x| x.delay_minutes = 60)
+            .unwrap();
+        (t, groups)
+    }
+
+    #[test]
+    fn reflow_moves_groups_behind_a_late_one() {
+        let (mut t, groups) = late(4);
+
+        let changes = t.reflow().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].group, groups[1]);
+        assert_eq!(changes[0].from, slot(&t, 1, 0));
+        assert_eq!(changes[0].to, Some(slot(&t, 2, 0)));
+
+        let group = t.get_group(&groups[1]).unwrap();
+        assert_eq!(group.get_arena().unwrap().id(), &slot(&t, 2, 0));
+        assert_eq!(
+            t.get_group(&groups[0]).unwrap().get_arena().unwrap().id(),
+            &slot(&t, 0, 0)
+        );
+    }
+
+    #[test]
+    fn reflow_keeps_groups_without_a_later_slot() {
+        let (mut t, groups) = late(2);
+
+        let changes = t.reflow().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].to, None);
+
+        let group = t.get_group(&groups[1]).unwrap();
+        assert_eq!(group.get_arena().unwrap().id(), &slot(&t, 1, 0));
+    }
+}
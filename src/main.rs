@@ -1,17 +1,17 @@
 use tournament_core::fencer_service::FencerService;
+use tournament_core::store::TournamentStore;
 use tournament_core::tournament::fencer_server::FencerServer;
 use tournament_core::tournament::tournament_server::TournamentServer;
 use tournament_core::tournament_core::Tournament;
 use tournament_core::tournament_service::TournamentService;
 
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use tonic::transport::Server;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let addr = "127.0.0.1:50051".parse()?;
-    let tournament = Arc::new(Mutex::new(Some(Tournament::new())));
+    let tournament = Arc::new(TournamentStore::new(Some(Tournament::new())));
     let tourn = TournamentService::new(tournament.clone());
     let fenc = FencerService::new(tournament);
 
@@ -0,0 +1,514 @@
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::arena_slot::ArenaSlotId;
+use crate::container::HasId;
+use crate::error::Error;
+use crate::group::GroupId;
+use crate::schedule::{Placement, Slot};
+use crate::scheduler::SchedulePlan;
+use crate::tournament_core::Tournament;
+
+/// cost of a group left without arena slots
+const UNPLACED_PENALTY: f64 = 10_000.0;
+/// cost of a double booked fencer or a group before its previous round
+const CONFLICT_PENALTY: f64 = 1_000.0;
+
+/// SplitMix64, results only depend on the seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// uniform in [0, 1)
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimizerSettings {
+    pub seed: u64,
+    /// the search stops when this is used up or after `max_iterations`,
+    /// only the latter gives the same result for the same seed
+    pub time_budget: Duration,
+    pub max_iterations: u64,
+    pub day_end_weight: f64,
+    pub idle_weight: f64,
+    pub piste_change_weight: f64,
+}
+
+impl Default for OptimizerSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            time_budget: Duration::from_secs(5),
+            max_iterations: 100_000,
+            day_end_weight: 1.0,
+            idle_weight: 0.5,
+            piste_change_weight: 30.0,
+        }
+    }
+}
+
+/// The parts the optimizer minimizes, `total` is their weighted sum.
+#[derive(Clone, Debug, Default)]
+pub struct Objective {
+    /// minutes from the start of each day to the end of its last group
+    pub day_end_minutes: i64,
+    /// minutes fencers wait between their groups of a day
+    pub idle_minutes: i64,
+    /// pistes a bewerb uses besides its first one
+    pub piste_changes: u32,
+    pub unplaced: u32,
+    pub fencer_conflicts: u32,
+    pub round_order_violations: u32,
+    pub total: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OptimizeResult {
+    /// start slots of all groups that have not started yet, see `reschedule`
+    pub plan: SchedulePlan,
+    pub objective: Objective,
+    /// objective of the schedule the search started from
+    pub initial: Objective,
+    pub iterations: u64,
+}
+
+/// Group index and its candidate before a change, to undo it.
+type Undo = Vec<(usize, Option<usize>)>;
+
+/// Assignment of the movable groups to one of their candidate runs.
+struct Search<'a> {
+    tournament: &'a Tournament,
+    settings: &'a OptimizerSettings,
+    fixed: Vec<Placement>,
+    groups: Vec<GroupId>,
+    fencers: Vec<Vec<u32>>,
+    candidates: Vec<Vec<Vec<Slot>>>,
+    assigned: Vec<Option<usize>>,
+    taken: HashMap<ArenaSlotId, usize>,
+}
+
+impl Search<'_> {
+    fn fits(&self, group: usize, candidate: usize) -> bool {
+        self.candidates[group][candidate]
+            .iter()
+            .all(|x| self.taken.get(&x.arena).is_none_or(|x| *x == group))
+    }
+
+    fn assign(&mut self, group: usize, candidate: Option<usize>) {
+        if let Some(old) = self.assigned[group] {
+            for slot in self.candidates[group][old].iter() {
+                self.taken.remove(&slot.arena);
+            }
+        }
+
+        self.assigned[group] = candidate;
+        if let Some(candidate) = candidate {
+            for slot in self.candidates[group][candidate].iter() {
+                self.taken.insert(slot.arena.clone(), group);
+            }
+        }
+    }
+
+    fn restore(&mut self, undo: &Undo) {
+        for (group, _) in undo.iter() {
+            self.assign(*group, None);
+        }
+        for (group, candidate) in undo.iter() {
+            self.assign(*group, *candidate);
+        }
+    }
+
+    /// Puts a random group into a random one of its candidates.
+    fn try_move(&mut self, rng: &mut Rng) -> Option<Undo> {
+        let group = rng.below(self.groups.len());
+        if self.candidates[group].is_empty() {
+            return None;
+        }
+
+        let candidate = rng.below(self.candidates[group].len());
+        let undo = vec![(group, self.assigned[group])];
+        self.assign(group, None);
+        if !self.fits(group, candidate) {
+            self.restore(&undo);
+            return None;
+        }
+
+        self.assign(group, Some(candidate));
+        Some(undo)
+    }
+
+    /// Exchanges the start slots of two random groups.
+    fn try_swap(&mut self, rng: &mut Rng) -> Option<Undo> {
+        let first = rng.below(self.groups.len());
+        let second = rng.below(self.groups.len());
+        let (Some(first_old), Some(second_old)) = (self.assigned[first], self.assigned[second])
+        else {
+            return None;
+        };
+        if first == second {
+            return None;
+        }
+
+        let start_of = |group: usize, candidate: usize| &self.candidates[group][candidate][0].arena;
+        let first_new = self.candidates[first]
+            .iter()
+            .position(|x| x[0].arena == *start_of(second, second_old))?;
+        let second_new = self.candidates[second]
+            .iter()
+            .position(|x| x[0].arena == *start_of(first, first_old))?;
+
+        let undo = vec![(first, Some(first_old)), (second, Some(second_old))];
+        self.assign(first, None);
+        self.assign(second, None);
+        if !self.fits(first, first_new) {
+            self.restore(&undo);
+            return None;
+        }
+        self.assign(first, Some(first_new));
+        if !self.fits(second, second_new) {
+            self.restore(&undo);
+            return None;
+        }
+        self.assign(second, Some(second_new));
+
+        Some(undo)
+    }
+
+    fn placements(&self) -> Vec<Placement> {
+        let mut res = self.fixed.clone();
+        for (group, candidate) in self.assigned.iter().enumerate() {
+            let Some(candidate) = candidate else {
+                continue;
+            };
+            let run = &self.candidates[group][*candidate];
+            if let Some(placement) =
+                Placement::new(self.groups[group].clone(), run, self.fencers[group].clone())
+            {
+                res.push(placement);
+            }
+        }
+        res
+    }
+
+    fn evaluate(&self) -> Objective {
+        let placements = self.placements();
+        let mut res = Objective {
+            unplaced: self.assigned.iter().filter(|x| x.is_none()).count() as u32,
+            ..Default::default()
+        };
+
+        let mut day_ends: HashMap<u32, NaiveDateTime> = HashMap::new();
+        let mut by_fencer: HashMap<u32, Vec<&Placement>> = HashMap::new();
+        let mut pistes: HashMap<u32, HashSet<u32>> = HashMap::new();
+        for placement in placements.iter() {
+            let end = day_ends
+                .entry(placement.slot.arena.day_id)
+                .or_insert(placement.slot.end);
+            *end = (*end).max(placement.slot.end);
+
+            for fencer in placement.fencers.iter() {
+                by_fencer.entry(*fencer).or_default().push(placement);
+            }
+
+            pistes
+                .entry(placement.group.bewerb_id)
+                .or_default()
+                .insert(placement.slot.piste);
+        }
+
+        for (day_id, end) in day_ends {
+            if let Some(day) = self.tournament.days.get(day_id) {
+                res.day_end_minutes += (end - day.date().and_time(day.start())).num_minutes();
+            }
+        }
+
+        for own in by_fencer.values_mut() {
+            own.sort_by_key(|x| x.slot.start);
+            for pair in own.windows(2) {
+                if pair[0].slot.overlaps(&pair[1].slot) {
+                    res.fencer_conflicts += 1;
+                } else if pair[0].slot.end.date() == pair[1].slot.start.date() {
+                    res.idle_minutes += (pair[1].slot.start - pair[0].slot.end).num_minutes();
+                }
+            }
+        }
+
+        res.piste_changes = pistes
+            .values()
+            .map(|x| x.len().saturating_sub(1) as u32)
+            .sum();
+        res.round_order_violations = self.tournament.round_order_warnings(&placements).len() as u32;
+
+        let settings = self.settings;
+        res.total = settings.day_end_weight * res.day_end_minutes as f64
+            + settings.idle_weight * res.idle_minutes as f64
+            + settings.piste_change_weight * res.piste_changes as f64
+            + UNPLACED_PENALTY * res.unplaced as f64
+            + CONFLICT_PENALTY * (res.fencer_conflicts + res.round_order_violations) as f64;
+        res
+    }
+
+    fn plan(&self) -> SchedulePlan {
+        let mut res = SchedulePlan::default();
+        for (group, candidate) in self.assigned.iter().enumerate() {
+            match candidate {
+                Some(candidate) => res.assignments.push((
+                    self.groups[group].clone(),
+                    self.candidates[group][*candidate][0].arena.clone(),
+                )),
+                None => res.unplaced.push(self.groups[group].clone()),
+            }
+        }
+        res
+    }
+}
+
+impl Tournament {
+    fn is_movable(&self, group: &GroupId) -> bool {
+        self.get_group(group).is_some_and(|x| {
+            let progress = x.get_progress();
+            progress.actual_start.is_none() && progress.actual_end.is_none()
+        })
+    }
+
+    /// Runs a group could be placed in apart from other movable groups:
+    /// allowed and available pistes with the required attributes, not
+    /// blocked for it, not used by a group that already started and while
+    /// all its fencers are available.
+    fn optimizer_candidates(
+        &self,
+        group: &GroupId,
+        fixed: &HashSet<ArenaSlotId>,
+    ) -> Vec<Vec<Slot>> {
+        let Some(bewerb) = self.bewerbs.get(group.bewerb_id) else {
+            return Vec::new();
+        };
+        let required = bewerb.get_required_attributes(group.round_id);
+        let minutes = self.get_group_minutes(group);
+        let fencers: Vec<u32> = self
+            .get_group(group)
+            .map(|x| x.get_fencers().iter().map(|f| f.get_id()).collect())
+            .unwrap_or_default();
+
+        self.get_slots()
+            .iter()
+            .filter(|x| bewerb.allows_piste(x.piste))
+            .filter(|x| self.piste_has_attributes(x.piste, &required))
+            .filter_map(|x| self.get_slot_run(&x.arena, minutes))
+            .filter(|run| {
+                run.iter().all(|x| {
+                    !fixed.contains(&x.arena)
+                        && self.get_arena(&x.arena).is_some_and(|a| a.allows(group))
+                })
+            })
+            .filter(|run| {
+                Placement::new(group.clone(), run, fencers.clone())
+                    .is_some_and(|p| self.get_unavailable_fencers(&fencers, &p.slot).is_empty())
+            })
+            .collect()
+    }
+
+    /// Searches for better arena slots for all groups that have not started
+    /// with simulated annealing. Starts from the current schedule with
+    /// `plan_schedule` filling the gaps and minimizes the weighted sum of
+    /// day ends, idle time of fencers and piste changes within bewerbs.
+    /// Nothing is changed, see `reschedule`.
+    pub fn optimize_schedule(&self, settings: &OptimizerSettings) -> OptimizeResult {
+        let placements = self.get_placements();
+        let (movable, mut fixed): (Vec<Placement>, Vec<Placement>) = placements
+            .into_iter()
+            .partition(|x| self.is_movable(&x.group));
+        let mut fixed_arenas: HashSet<ArenaSlotId> = fixed
+            .iter()
+            .flat_map(|x| x.arenas.iter().cloned())
+            .chain(self.get_bout_arenas())
+            .collect();
+
+        // groups whose current run is no candidate anymore, e.g. after an
+        // availability changed, stay where they are instead of being left
+        // unplaced while others are planned into their arena slots
+        let (movable, stuck): (Vec<Placement>, Vec<Placement>) =
+            movable.into_iter().partition(|x| {
+                self.optimizer_candidates(&x.group, &fixed_arenas)
+                    .iter()
+                    .any(|run| run[0].arena == x.slot.arena)
+            });
+        let stuck_groups: Vec<GroupId> = stuck.iter().map(|x| x.group.clone()).collect();
+        fixed_arenas.extend(stuck.iter().flat_map(|x| x.arenas.iter().cloned()));
+        fixed.extend(stuck);
+
+        let groups: Vec<GroupId> = self
+            .all_groups()
+            .iter()
+            .map(|x| x.id())
+            .filter(|x| self.is_movable(x) && !stuck_groups.contains(x))
+            .collect();
+
+        let mut search = Search {
+            tournament: self,
+            settings,
+            fixed,
+            fencers: groups
+                .iter()
+                .map(|x| {
+                    self.get_group(x)
+                        .map(|g| g.get_fencers().iter().map(|f| f.get_id()).collect())
+                        .unwrap_or_default()
+                })
+                .collect(),
+            candidates: groups
+                .iter()
+                .map(|x| self.optimizer_candidates(x, &fixed_arenas))
+                .collect(),
+            assigned: vec![None; groups.len()],
+            taken: HashMap::new(),
+            groups,
+        };
+
+        let proposed = self.plan_schedule().assignments;
+        let starts = movable
+            .iter()
+            .map(|x| (x.group.clone(), x.slot.arena.clone()))
+            .chain(proposed);
+        for (group_id, arena) in starts {
+            let Some(group) = search.groups.iter().position(|x| *x == group_id) else {
+                continue;
+            };
+            let candidate = search.candidates[group]
+                .iter()
+                .position(|x| x[0].arena == arena);
+            if let Some(candidate) = candidate.filter(|x| search.fits(group, *x)) {
+                search.assign(group, Some(candidate));
+            }
+        }
+
+        let mut rng = Rng(settings.seed);
+        let mut current = search.evaluate();
+        let initial = current.clone();
+        let mut best = (current.clone(), search.assigned.clone());
+
+        let started = Instant::now();
+        let t0 = (current.total.abs() * 0.05).max(1.0);
+        let mut iterations = 0;
+        while !search.groups.is_empty()
+            && iterations < settings.max_iterations
+            && started.elapsed() < settings.time_budget
+        {
+            iterations += 1;
+            let progress = (iterations as f64 / settings.max_iterations as f64).max(
+                started.elapsed().as_secs_f64() / settings.time_budget.as_secs_f64().max(1e-9),
+            );
+            let temperature = t0 * (1.0 - progress).max(1e-3);
+
+            let undo = if rng.below(2) == 0 {
+                search.try_move(&mut rng)
+            } else {
+                search.try_swap(&mut rng)
+            };
+            let Some(undo) = undo else {
+                continue;
+            };
+
+            let next = search.evaluate();
+            let delta = next.total - current.total;
+            if delta <= 0.0 || rng.unit() < (-delta / temperature).exp() {
+                current = next;
+                if current.total < best.0.total {
+                    best = (current.clone(), search.assigned.clone());
+                }
+            } else {
+                search.restore(&undo);
+            }
+        }
+
+        let (objective, assigned) = best;
+        search.assigned = assigned;
+
+        OptimizeResult {
+            plan: search.plan(),
+            objective,
+            initial,
+            iterations,
+        }
+    }
+
+    /// Moves the groups of `plan` to their new arena slots, all or none.
+    /// Unlike `apply_plan` the groups may already be scheduled.
+    pub fn reschedule(&mut self, plan: &SchedulePlan) -> Result<(), Error> {
+        self.transaction(|next| {
+            for (group_id, _) in plan.assignments.iter() {
+                next.freeup_group(group_id)?;
+            }
+            next.apply_plan(plan)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{add_bewerb, tournament};
+    use crate::validation::ScheduleWarning;
+
+    /// Two bewerbs whose fencers fence every round, so groups of
+    /// consecutive rounds must not overlap.
+    fn two_bewerbs() -> Tournament {
+        let mut res = tournament(6, 3);
+        add_bewerb(&mut res, "Foil", 2, 3, 5);
+        add_bewerb(&mut res, "Epee", 2, 2, 4);
+        res
+    }
+
+    fn settings() -> OptimizerSettings {
+        OptimizerSettings {
+            seed: 42,
+            time_budget: Duration::from_secs(3600),
+            max_iterations: 2_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_plan() {
+        let tournament = two_bewerbs();
+        let settings = settings();
+
+        let first = tournament.optimize_schedule(&settings);
+        let second = tournament.optimize_schedule(&settings);
+        assert_eq!(first.iterations, second.iterations);
+        assert_eq!(first.objective.total, second.objective.total);
+        assert_eq!(first.plan.assignments, second.plan.assignments);
+        assert_eq!(first.plan.unplaced, second.plan.unplaced);
+    }
+
+    #[test]
+    fn optimized_plan_has_no_conflicts() {
+        let mut tournament = two_bewerbs();
+        let res = tournament.optimize_schedule(&settings());
+        assert_eq!(res.objective.unplaced, 0);
+        assert_eq!(res.objective.fencer_conflicts, 0);
+        assert_eq!(res.objective.round_order_violations, 0);
+        assert!(res.objective.total <= res.initial.total);
+
+        tournament.reschedule(&res.plan).unwrap();
+        assert!(!tournament.validate_schedule().iter().any(|x| matches!(
+            x,
+            ScheduleWarning::FencerDoubleBooked { .. } | ScheduleWarning::RoundOrder { .. }
+        )));
+    }
+}
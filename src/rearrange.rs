@@ -0,0 +1,183 @@
+use crate::arena_slot::ArenaSlotId;
+use crate::error::Error;
+use crate::group::Group;
+use crate::tournament_core::Tournament;
+use crate::validation::ScheduleWarning;
+use std::sync::Arc;
+
+/// One change of a batch passed to `apply_edits`.
+#[derive(Clone, Debug)]
+pub enum ScheduleEdit {
+    /// moves the group starting in `from` to start in `to`
+    Move { from: ArenaSlotId, to: ArenaSlotId },
+    /// exchanges the groups starting in the two arena slots
+    Swap {
+        first: ArenaSlotId,
+        second: ArenaSlotId,
+    },
+}
+
+impl Tournament {
+    /// Runs `f` on a copy and keeps the copy only if `f` succeeded, so a
+    /// failing edit never leaves the schedule half changed. Within
+    /// `TournamentStore::write` or another transaction `self` already is
+    /// such a copy and `f` runs on it directly.
+    pub(crate) fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Tournament) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if self.in_write {
+            return f(self);
+        }
+
+        let mut next = self.deep_clone();
+        next.in_write = true;
+        let res = f(&mut next)?;
+        next.in_write = false;
+        *self = next;
+        Ok(res)
+    }
+
+    /// The group in the arena slot if it starts there, not if it only
+    /// continues from an earlier slot.
+    fn get_group_starting_in(&self, id: &ArenaSlotId) -> Option<Arc<Group>> {
+        self.get_arena(id)
+            .and_then(|x| x.get_group())
+            .filter(|x| x.get_arena().is_some_and(|a| a.id() == id))
+    }
+
+    fn apply_edit(&mut self, edit: &ScheduleEdit) -> Result<(), Error> {
+        match edit {
+            ScheduleEdit::Move { from, to } => {
+                let Some(group) = self.get_group_starting_in(from) else {
+                    return Err(Error::InvalidInput(format!(
+                        "no group starts in arena {:?}",
+                        from
+                    )));
+                };
+                self.add_group_to_arena(&group.id(), to)?;
+            }
+            ScheduleEdit::Swap { first, second } => {
+                let first_group = self.get_group_starting_in(first);
+                let second_group = self.get_group_starting_in(second);
+                let (Some(first_group), Some(second_group)) = (first_group, second_group) else {
+                    return Err(Error::InvalidInput(format!(
+                        "no group starts in arena {:?} or {:?}",
+                        first, second
+                    )));
+                };
+
+                self.freeup_group(&first_group.id())?;
+                self.freeup_group(&second_group.id())?;
+                self.add_group_to_arena(&first_group.id(), second)?;
+                self.add_group_to_arena(&second_group.id(), first)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the group starting in `from` to start in `to`. Fails without
+    /// changes if `to` or a following slot it needs is used by another
+    /// group.
+    pub fn move_group(
+        &mut self,
+        from: &ArenaSlotId,
+        to: &ArenaSlotId,
+    ) -> Result<Vec<ScheduleWarning>, Error> {
+        self.apply_edits(&[ScheduleEdit::Move {
+            from: from.clone(),
+            to: to.clone(),
+        }])
+    }
+
+    /// Exchanges the groups starting in the two arena slots.
+    pub fn swap_groups(
+        &mut self,
+        first: &ArenaSlotId,
+        second: &ArenaSlotId,
+    ) -> Result<Vec<ScheduleWarning>, Error> {
+        self.apply_edits(&[ScheduleEdit::Swap {
+            first: first.clone(),
+            second: second.clone(),
+        }])
+    }
+
+    /// Applies all edits in order or, if one of them fails, none of them.
+    /// Returns the warnings of the whole schedule afterwards.
+    pub fn apply_edits(&mut self, edits: &[ScheduleEdit]) -> Result<Vec<ScheduleWarning>, Error> {
+        self.transaction(|next| {
+            for edit in edits {
+                next.apply_edit(edit)?;
+            }
+            Ok(next.validate_schedule())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::GroupId;
+    use crate::test_support::{add_bewerb, groups, slot, tournament};
+
+    fn start_of(tournament: &Tournament, group: &GroupId) -> Option<ArenaSlotId> {
+        let group = tournament.get_group(group)?;
+        group.get_arena().map(|x| x.id().clone())
+    }
+
+    /// Two groups of one round, the first on piste 0 and the second on
+    /// piste 1 of the first timeslot.
+    fn scheduled() -> (Tournament, Vec<GroupId>) {
+        let mut t = tournament(4, 2);
+        let bewerb_id = add_bewerb(&mut t, "Foil", 1, 2, 4);
+        let groups = groups(&t, bewerb_id);
+        for (piste, group) in groups.iter().enumerate() {
+            let arena = slot(&t, 0, piste as u32);
+            t.add_group_to_arena(group, &arena).unwrap();
+        }
+        (t, groups)
+    }
+
+    #[test]
+    fn move_and_swap() {
+        let (mut t, groups) = scheduled();
+
+        t.move_group(&slot(&t, 0, 0), &slot(&t, 2, 0)).unwrap();
+        assert_eq!(start_of(&t, &groups[0]), Some(slot(&t, 2, 0)));
+        assert!(t.get_arena(&slot(&t, 0, 0)).unwrap().is_free());
+
+        t.swap_groups(&slot(&t, 2, 0), &slot(&t, 0, 1)).unwrap();
+        assert_eq!(start_of(&t, &groups[0]), Some(slot(&t, 0, 1)));
+        assert_eq!(start_of(&t, &groups[1]), Some(slot(&t, 2, 0)));
+    }
+
+    #[test]
+    fn move_onto_another_group_changes_nothing() {
+        let (mut t, groups) = scheduled();
+
+        assert!(t.move_group(&slot(&t, 0, 0), &slot(&t, 0, 1)).is_err());
+        assert_eq!(start_of(&t, &groups[0]), Some(slot(&t, 0, 0)));
+        assert_eq!(start_of(&t, &groups[1]), Some(slot(&t, 0, 1)));
+        assert!(!t.in_write);
+    }
+
+    #[test]
+    fn failing_edit_undoes_the_batch() {
+        let (mut t, groups) = scheduled();
+
+        let edits = [
+            ScheduleEdit::Move {
+                from: slot(&t, 0, 0),
+                to: slot(&t, 1, 0),
+            },
+            ScheduleEdit::Swap {
+                first: slot(&t, 0, 1),
+                second: slot(&t, 3, 1),
+            },
+        ];
+        assert!(t.apply_edits(&edits).is_err());
+        assert_eq!(start_of(&t, &groups[0]), Some(slot(&t, 0, 0)));
+        assert_eq!(start_of(&t, &groups[1]), Some(slot(&t, 0, 1)));
+        assert!(t.get_arena(&slot(&t, 1, 0)).unwrap().is_free());
+    }
+}
@@ -0,0 +1,274 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use std::fmt::Write;
+
+use crate::container::HasId;
+use crate::day::Day;
+use crate::tournament_core::Tournament;
+
+/// Usage of one piste on one day.
+#[derive(Clone, Debug, Default)]
+pub struct PisteUsage {
+    pub piste: u32,
+    pub piste_name: String,
+    pub occupied: u32,
+    pub free: u32,
+    pub blocked: u32,
+    pub occupied_minutes: i64,
+    pub free_minutes: i64,
+    /// runs of free slots with a group or bout before and after them
+    pub idle_gaps: u32,
+    pub idle_gap_minutes: i64,
+}
+
+/// Slots of the day used by one bewerb.
+#[derive(Clone, Debug, Default)]
+pub struct BewerbShare {
+    pub bewerb_id: u32,
+    pub bewerb_name: String,
+    pub occupied: u32,
+    /// part of all occupied slots of the day, 0 to 1
+    pub share: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DayUsage {
+    pub day_id: u32,
+    pub date: NaiveDate,
+    pub occupied: u32,
+    pub free: u32,
+    pub blocked: u32,
+    /// occupied part of all slots that are not blocked, 0 to 1
+    pub utilization: f64,
+    /// most groups and bouts fenced at the same time and the start of that
+    /// timeslot
+    pub peak_concurrent: u32,
+    pub peak_start: Option<NaiveDateTime>,
+    pub bewerbs: Vec<BewerbShare>,
+    pub pistes: Vec<PisteUsage>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UtilizationReport {
+    pub days: Vec<DayUsage>,
+}
+
+#[derive(Clone, Copy)]
+enum SlotState {
+    Occupied,
+    Free,
+    Blocked,
+}
+
+/// The slots of one piste in timeslot order with their minutes.
+type Lane = (u32, Vec<(i64, SlotState)>);
+
+fn ratio(part: u32, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+impl PisteUsage {
+    pub fn utilization(&self) -> f64 {
+        ratio(self.occupied, self.occupied + self.free)
+    }
+}
+
+impl UtilizationReport {
+    /// One line per day and piste.
+    pub fn pistes_csv(&self) -> String {
+        let mut res = String::from(
+            "date,day_id,piste,piste_name,occupied,free,blocked,utilization,idle_gaps,idle_gap_minutes\n",
+        );
+        for day in self.days.iter() {
+            for piste in day.pistes.iter() {
+                let _ = writeln!(
+                    res,
+                    "{},{},{},{},{},{},{},{:.3},{},{}",
+                    day.date,
+                    day.day_id,
+                    piste.piste,
+                    csv_field(&piste.piste_name),
+                    piste.occupied,
+                    piste.free,
+                    piste.blocked,
+                    piste.utilization(),
+                    piste.idle_gaps,
+                    piste.idle_gap_minutes
+                );
+            }
+        }
+        res
+    }
+
+    /// One line per day with its totals and peak load.
+    pub fn days_csv(&self) -> String {
+        let mut res = String::from(
+            "date,day_id,occupied,free,blocked,utilization,peak_concurrent,peak_start\n",
+        );
+        for day in self.days.iter() {
+            let peak_start = day.peak_start.map(|x| x.to_string()).unwrap_or_default();
+            let _ = writeln!(
+                res,
+                "{},{},{},{},{},{:.3},{},{}",
+                day.date,
+                day.day_id,
+                day.occupied,
+                day.free,
+                day.blocked,
+                day.utilization,
+                day.peak_concurrent,
+                peak_start
+            );
+        }
+        res
+    }
+
+    /// One line per day and bewerb.
+    pub fn bewerbs_csv(&self) -> String {
+        let mut res = String::from("date,day_id,bewerb_id,bewerb_name,occupied,share\n");
+        for day in self.days.iter() {
+            for bewerb in day.bewerbs.iter() {
+                let _ = writeln!(
+                    res,
+                    "{},{},{},{},{},{:.3}",
+                    day.date,
+                    day.day_id,
+                    bewerb.bewerb_id,
+                    csv_field(&bewerb.bewerb_name),
+                    bewerb.occupied,
+                    bewerb.share
+                );
+            }
+        }
+        res
+    }
+}
+
+impl Tournament {
+    fn get_day_usage(&self, day: &Day) -> DayUsage {
+        let mut res = DayUsage {
+            day_id: day.get_id(),
+            date: day.date(),
+            ..Default::default()
+        };
+
+        let mut lanes: Vec<Lane> = Vec::new();
+        for ts in day.timeslots() {
+            let mut concurrent = 0;
+            for arena in ts.get_all_arenas() {
+                if !day.is_piste_enabled(ts.get_id(), arena.piste) {
+                    continue;
+                }
+
+                let occupant = arena.get_occupant();
+                let state = match (&occupant, arena.get_block()) {
+                    (Some(_), _) => SlotState::Occupied,
+                    (None, Some(_)) => SlotState::Blocked,
+                    (None, None) => SlotState::Free,
+                };
+
+                if let Some(occupant) = occupant {
+                    concurrent += 1;
+                    let bewerb_id = occupant.bewerb_id();
+                    match res.bewerbs.iter_mut().find(|x| x.bewerb_id == bewerb_id) {
+                        Some(share) => share.occupied += 1,
+                        None => res.bewerbs.push(BewerbShare {
+                            bewerb_id,
+                            bewerb_name: occupant.bewerb_name(),
+                            occupied: 1,
+                            share: 0.0,
+                        }),
+                    }
+                }
+
+                let minutes = ts.duration as i64;
+                match lanes.iter_mut().find(|(piste, _)| *piste == arena.piste) {
+                    Some((_, lane)) => lane.push((minutes, state)),
+                    None => lanes.push((arena.piste, vec![(minutes, state)])),
+                }
+            }
+
+            if concurrent > res.peak_concurrent {
+                res.peak_concurrent = concurrent;
+                res.peak_start = Some(day.date().and_time(ts.start));
+            }
+        }
+
+        lanes.sort_by_key(|(piste, _)| *piste);
+        for (piste, lane) in lanes {
+            let mut usage = PisteUsage {
+                piste,
+                piste_name: self.piste_name(piste),
+                ..Default::default()
+            };
+
+            let mut seen_group = false;
+            let mut gap: Option<i64> = None;
+            for (minutes, state) in lane {
+                match state {
+                    SlotState::Occupied => {
+                        usage.occupied += 1;
+                        usage.occupied_minutes += minutes;
+                        if let Some(gap_minutes) = gap.take() {
+                            usage.idle_gaps += 1;
+                            usage.idle_gap_minutes += gap_minutes;
+                        }
+                        seen_group = true;
+                    }
+                    SlotState::Free => {
+                        usage.free += 1;
+                        usage.free_minutes += minutes;
+                        if seen_group {
+                            gap = Some(gap.unwrap_or_default() + minutes);
+                        }
+                    }
+                    SlotState::Blocked => usage.blocked += 1,
+                }
+            }
+
+            res.occupied += usage.occupied;
+            res.free += usage.free;
+            res.blocked += usage.blocked;
+            res.pistes.push(usage);
+        }
+
+        res.utilization = ratio(res.occupied, res.occupied + res.free);
+        for share in res.bewerbs.iter_mut() {
+            share.share = ratio(share.occupied, res.occupied);
+        }
+        res
+    }
+
+    /// Occupied and free arena slots, idle gaps, bewerb shares and peak
+    /// load of every day and piste. Unavailable pistes are left out.
+    pub fn get_utilization_report(&self) -> UtilizationReport {
+        UtilizationReport {
+            days: self.days.iter().map(|x| self.get_day_usage(x)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("Blue"), "Blue");
+        assert_eq!(csv_field("Blue, left"), "\"Blue, left\"");
+        assert_eq!(csv_field("the \"Final\""), "\"the \"\"Final\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field(""), "");
+    }
+}
@@ -1,6 +1,7 @@
 use crate::bewerb::BewerbId;
-use crate::container::HasId;
+use crate::container::{HasId, IdAllocator, UidContainer};
 use crate::group::{Group, GroupId, GroupSaveable};
+use crate::venue::PisteAttribute;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -14,13 +15,17 @@ pub struct RoundId {
 #[derive(Debug, Default)]
 pub struct Round {
     id: RoundId,
-    groups: Vec<Arc<Group>>,
+    groups: UidContainer<Arc<Group>>,
+    /// the groups of the round have to be fenced on pistes with these
+    pub required_attributes: Vec<PisteAttribute>,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RoundSaveable {
     id: RoundId,
     groups: Vec<GroupSaveable>,
+    #[serde(default)]
+    required_attributes: Vec<PisteAttribute>,
 }
 
 impl From<&Round> for RoundSaveable {
@@ -29,38 +34,41 @@ impl From<&Round> for RoundSaveable {
         Self {
             id: round.id.clone(),
             groups,
+            required_attributes: round.required_attributes.clone(),
         }
     }
 }
 
 impl Round {
     pub fn from_saveable(round: &RoundSaveable) -> Self {
-        let mut groups: Vec<Arc<Group>> = Default::default();
+        let mut groups: UidContainer<Arc<Group>> = Default::default();
         for group in &round.groups {
-            groups.push(Arc::new(Group::from_saveable(group)));
+            groups.insert(Arc::new(Group::from_saveable(group)));
         }
 
         Self {
             id: round.id.clone(),
             groups,
+            required_attributes: round.required_attributes.clone(),
         }
     }
 
-    pub fn new(bewerb_id: &BewerbId, n_groups: u32, round_id: u32) -> Self {
+    pub fn new(ids: &mut IdAllocator, bewerb_id: &BewerbId, n_groups: u32) -> Self {
+        let round_id = ids.next();
         let mut res = Self::default();
         res.id.bewerb_name = bewerb_id.bewerb_name.clone();
         res.id.bewerb_id = bewerb_id.bewerb_id;
         res.id.round_id = round_id;
-        for i in 0..n_groups {
+        for _ in 0..n_groups {
             let id = GroupId {
                 bewerb_name: bewerb_id.bewerb_name.clone(),
                 bewerb_id: bewerb_id.bewerb_id,
                 round_id,
-                group_id: i,
+                group_id: ids.next(),
             };
 
             let group = Group::new(id);
-            res.groups.push(Arc::new(group));
+            res.groups.insert(Arc::new(group));
         }
         res
     }
@@ -78,10 +86,7 @@ impl Round {
     }
 
     pub fn get_group_by_id(&self, id: &GroupId) -> Option<Arc<Group>> {
-        self.groups
-            .iter()
-            .find(|x| x.id().group_id == id.group_id)
-            .cloned()
+        self.groups.get(id.group_id).cloned()
     }
 }
 
@@ -89,7 +94,4 @@ impl HasId for Round {
     fn get_id(&self) -> u32 {
         self.id.round_id
     }
-    fn set_id(&mut self, id: u32) {
-        self.id.round_id = id;
-    }
 }
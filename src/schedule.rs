@@ -0,0 +1,310 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::arena_slot::ArenaSlotId;
+use crate::container::HasId;
+use crate::error::Error;
+use crate::group::GroupId;
+use crate::tableau::BoutId;
+use crate::tournament_core::Tournament;
+
+/// An arena slot together with the time it covers.
+#[derive(Clone, Debug)]
+pub struct Slot {
+    pub arena: ArenaSlotId,
+    pub piste: u32,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl Slot {
+    pub fn overlaps(&self, other: &Slot) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// The time a run of slots covers, starting in its first slot.
+    pub fn span(run: &[Slot]) -> Option<Slot> {
+        let first = run.first()?;
+        let last = run.last()?;
+
+        Some(Slot {
+            arena: first.arena.clone(),
+            piste: first.piste,
+            start: first.start,
+            end: last.end,
+        })
+    }
+}
+
+/// Where and when a group is fenced.
+#[derive(Clone, Debug)]
+pub struct Placement {
+    pub group: GroupId,
+    /// the whole time the group occupies, `slot.arena` is the first slot
+    pub slot: Slot,
+    pub arenas: Vec<ArenaSlotId>,
+    pub fencers: Vec<u32>,
+}
+
+impl Placement {
+    pub fn new(group: GroupId, run: &[Slot], fencers: Vec<u32>) -> Option<Self> {
+        Some(Self {
+            group,
+            slot: Slot::span(run)?,
+            arenas: run.iter().map(|x| x.arena.clone()).collect(),
+            fencers,
+        })
+    }
+}
+
+/// A group of a fencer with where and when it is fenced.
+#[derive(Clone, Debug)]
+pub struct FencerGroupEntry {
+    pub group: GroupId,
+    pub day_id: u32,
+    pub date: NaiveDate,
+    pub timeslot_id: u32,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub piste: u32,
+    pub piste_name: String,
+}
+
+/// An elimination bout of a fencer with where and when it is fenced.
+#[derive(Clone, Debug)]
+pub struct FencerBoutEntry {
+    pub bout: BoutId,
+    /// name of the table, e.g. "T32"
+    pub table: String,
+    pub day_id: u32,
+    pub date: NaiveDate,
+    pub timeslot_id: u32,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub piste: u32,
+    pub piste_name: String,
+}
+
+/// A round of a bewerb of the fencer whose group or arena slot is not
+/// known yet, `group` is set if only the arena slot is missing.
+#[derive(Clone, Debug)]
+pub struct PendingRound {
+    pub bewerb_id: u32,
+    pub bewerb_name: String,
+    pub round_id: u32,
+    pub group: Option<GroupId>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FencerSchedule {
+    pub fencer: u32,
+    /// ordered by start
+    pub groups: Vec<FencerGroupEntry>,
+    /// bouts the fencer is known to fence, ordered by start
+    pub bouts: Vec<FencerBoutEntry>,
+    pub pending: Vec<PendingRound>,
+}
+
+impl Tournament {
+    /// All arena slots on available pistes ordered by start time, then by
+    /// piste.
+    pub fn get_slots(&self) -> Vec<Slot> {
+        let mut res = Vec::new();
+        for day in self.days.iter() {
+            for ts in day.timeslots() {
+                let Some((start, end)) = day.get_timeslot_range(ts.get_id()) else {
+                    continue;
+                };
+
+                for arena in ts.get_all_arenas() {
+                    if !day.is_piste_enabled(ts.get_id(), arena.piste) {
+                        continue;
+                    }
+
+                    res.push(Slot {
+                        arena: arena.id().clone(),
+                        piste: arena.piste,
+                        start,
+                        end,
+                    });
+                }
+            }
+        }
+
+        res.sort_by_key(|x| (x.start, x.piste));
+        res
+    }
+
+    pub fn get_slot(&self, id: &ArenaSlotId) -> Option<Slot> {
+        let day = self.days.get(id.day_id)?;
+        let arena = day.get_timeslot(id.timeslot_id)?.get_arena(id)?;
+        let (start, end) = day.get_timeslot_range(id.timeslot_id)?;
+
+        Some(Slot {
+            arena: id.clone(),
+            piste: arena.piste,
+            start,
+            end,
+        })
+    }
+
+    /// The arena slots a group of `minutes` length occupies when it starts
+    /// in `start`: the slots of the same piste in the following timeslots
+    /// of the day until their durations add up. `None` if the day ends
+    /// before, a gap or break comes in between or the piste is not
+    /// available in one of the timeslots.
+    pub fn get_slot_run(&self, start: &ArenaSlotId, minutes: u32) -> Option<Vec<Slot>> {
+        let day = self.days.get(start.day_id)?;
+        let first = self.get_slot(start)?;
+
+        let mut res = Vec::new();
+        let mut covered = 0;
+        for ts in day
+            .timeslots()
+            .skip_while(|x| x.get_id() != start.timeslot_id)
+        {
+            let arena = ts
+                .get_all_arenas()
+                .into_iter()
+                .find(|x| x.piste == first.piste)?;
+            if !day.is_piste_enabled(ts.get_id(), arena.piste) {
+                return None;
+            }
+            let slot = self.get_slot(arena.id())?;
+            if res.last().is_some_and(|x: &Slot| x.end != slot.start) {
+                return None;
+            }
+            res.push(slot);
+
+            covered += ts.duration;
+            if covered >= minutes {
+                return Some(res);
+            }
+        }
+        None
+    }
+
+    /// Free arena slots the group could start in: available, not blocked
+    /// for it and followed by enough free slots of the piste.
+    pub fn get_free_arenas_for(&self, group: &GroupId) -> Vec<ArenaSlotId> {
+        let minutes = self.get_group_minutes(group);
+        self.get_slots()
+            .into_iter()
+            .filter(|slot| {
+                self.get_slot_run(&slot.arena, minutes).is_some_and(|run| {
+                    run.iter().all(|x| {
+                        self.get_arena(&x.arena)
+                            .is_some_and(|a| a.is_free() && a.allows(group))
+                    })
+                })
+            })
+            .map(|x| x.arena)
+            .collect()
+    }
+
+    /// Estimated length of the group from its bouts and weapon.
+    pub fn get_group_minutes(&self, group: &GroupId) -> u32 {
+        let Some(bewerb) = self.bewerbs.get(group.bewerb_id) else {
+            return 0;
+        };
+        bewerb
+            .get_group_by_id(group)
+            .map(|x| x.estimated_minutes(bewerb.weapon))
+            .unwrap_or_default()
+    }
+
+    /// Where and when the fencer fences their groups and elimination bouts
+    /// in all of their bewerbs.
+    pub fn get_fencer_schedule(&self, fencer_id: u32) -> Result<FencerSchedule, Error> {
+        let Some(fencer) = self.fencers.get(fencer_id) else {
+            return Err(Error::InvalidInput(format!(
+                "Ivalid fencer_id {:?}",
+                fencer_id
+            )));
+        };
+
+        let placements = self.get_placements();
+        let groups = fencer.get_groups();
+        let mut res = FencerSchedule {
+            fencer: fencer_id,
+            ..Default::default()
+        };
+
+        for bewerb_id in fencer.get_bewerb_ids() {
+            let Some(bewerb) = self.bewerbs.get(bewerb_id) else {
+                continue;
+            };
+
+            for round_id in bewerb.get_round_ids() {
+                let group = groups
+                    .iter()
+                    .map(|x| x.id())
+                    .find(|x| x.bewerb_id == bewerb_id && x.round_id == round_id);
+                let placement = group
+                    .as_ref()
+                    .and_then(|id| placements.iter().find(|x| x.group == *id));
+
+                let Some(placement) = placement else {
+                    res.pending.push(PendingRound {
+                        bewerb_id,
+                        bewerb_name: bewerb.name().to_owned(),
+                        round_id,
+                        group,
+                    });
+                    continue;
+                };
+
+                let slot = &placement.slot;
+                res.groups.push(FencerGroupEntry {
+                    group: placement.group.clone(),
+                    day_id: slot.arena.day_id,
+                    date: slot.start.date(),
+                    timeslot_id: slot.arena.timeslot_id,
+                    start: slot.start,
+                    end: slot.end,
+                    piste: slot.piste,
+                    piste_name: self.piste_name(slot.piste),
+                });
+            }
+        }
+
+        for placement in self.get_bout_placements() {
+            if !placement.fencers.contains(&fencer_id) {
+                continue;
+            }
+
+            let slot = &placement.slot;
+            res.bouts.push(FencerBoutEntry {
+                bout: placement.bout.clone(),
+                table: placement.table.clone(),
+                day_id: slot.arena.day_id,
+                date: slot.start.date(),
+                timeslot_id: slot.arena.timeslot_id,
+                start: slot.start,
+                end: slot.end,
+                piste: slot.piste,
+                piste_name: self.piste_name(slot.piste),
+            });
+        }
+
+        res.groups.sort_by_key(|x| x.start);
+        Ok(res)
+    }
+
+    /// All groups that are put into an arena slot.
+    pub fn get_placements(&self) -> Vec<Placement> {
+        let mut res = Vec::new();
+        for group in self.all_groups() {
+            let run: Vec<Slot> = group
+                .get_arenas()
+                .iter()
+                .filter_map(|x| self.get_slot(x.id()))
+                .collect();
+            let fencers = group.get_fencers().iter().map(|x| x.get_id()).collect();
+
+            if let Some(placement) = Placement::new(group.id(), &run, fencers) {
+                res.push(placement);
+            }
+        }
+        res
+    }
+}
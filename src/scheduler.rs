@@ -0,0 +1,253 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use std::collections::HashSet;
+
+use crate::arena_slot::ArenaSlotId;
+use crate::bewerb::Bewerb;
+use crate::container::HasId;
+use crate::error::Error;
+use crate::group::GroupId;
+use crate::schedule::{Placement, Slot};
+use crate::tournament_core::Tournament;
+
+/// Proposed arena slots for groups that are not scheduled yet.
+#[derive(Clone, Debug, Default)]
+pub struct SchedulePlan {
+    pub assignments: Vec<(GroupId, ArenaSlotId)>,
+    /// groups for which no fitting arena slot was found
+    pub unplaced: Vec<GroupId>,
+}
+
+/// The placements a plan is built on, the existing ones plus those planned
+/// so far.
+struct PlanState {
+    placements: Vec<Placement>,
+    taken: HashSet<ArenaSlotId>,
+}
+
+impl PlanState {
+    fn get(&self, group: &GroupId) -> Option<&Placement> {
+        self.placements.iter().find(|x| x.group == *group)
+    }
+
+    /// Whether a group of one of `fencers` overlaps `slot` or leaves them
+    /// less than `min_rest` before or after it.
+    fn has_fencer_conflict(&self, slot: &Slot, fencers: &[u32], min_rest: TimeDelta) -> bool {
+        self.placements
+            .iter()
+            .filter(|x| x.slot.start - min_rest < slot.end && slot.start < x.slot.end + min_rest)
+            .any(|x| x.fencers.iter().any(|f| fencers.contains(f)))
+    }
+
+    fn add(&mut self, placement: Placement) {
+        self.taken.extend(placement.arenas.iter().cloned());
+        self.placements.push(placement);
+    }
+}
+
+impl Tournament {
+    /// End of the round fenced before `round_id` plus the gap between
+    /// rounds, `None` if a group of that round is not scheduled.
+    fn previous_round_end(
+        &self,
+        bewerb: &Bewerb,
+        round_id: u32,
+        state: &PlanState,
+    ) -> Option<Option<NaiveDateTime>> {
+        let round_ids = bewerb.get_round_ids();
+        let pos = round_ids.iter().position(|x| *x == round_id)?;
+        if pos == 0 {
+            return Some(None);
+        }
+
+        let mut res = None;
+        for group in bewerb.get_groups_of_round(round_ids[pos - 1]) {
+            let placement = state.get(&group)?;
+            res = res.max(Some(placement.slot.end));
+        }
+
+        let gap = TimeDelta::minutes(self.inner.round_gap_minutes as i64);
+        Some(res.map(|x| x + gap))
+    }
+
+    /// Arena slots in the order the scheduler tries them for `bewerb`,
+    /// slots on preferred days first.
+    fn candidate_slots<'a>(bewerb: &Bewerb, slots: &'a [Slot]) -> Vec<&'a Slot> {
+        let allowed = slots.iter().filter(|x| bewerb.allows_piste(x.piste));
+        let (mut res, mut others): (Vec<&Slot>, Vec<&Slot>) =
+            allowed.partition(|x| bewerb.preferred_days.contains(&x.arena.day_id));
+        res.append(&mut others);
+        res
+    }
+
+    /// Proposes arena slots for all groups that are not scheduled yet.
+    ///
+    /// Groups are placed bewerb by bewerb in round order into the earliest
+    /// free slot that starts after the previous round has ended, is on an
+    /// allowed piste and leaves the fencers at least `min_rest_minutes`
+    /// before and after their other groups. Rounds with required attributes only use pistes having
+    /// them, blocked slots and slots reserved for others are skipped, and
+    /// so are times a fencer of the group is not available.
+    /// Groups longer than a timeslot also need the following slots of the
+    /// piste to be free. Nothing is changed, see `apply_plan`.
+    pub fn plan_schedule(&self) -> SchedulePlan {
+        let slots = self.get_slots();
+        let placements = self.get_placements();
+        let mut state = PlanState {
+            taken: placements
+                .iter()
+                .flat_map(|x| x.arenas.iter().cloned())
+                .chain(self.get_bout_arenas())
+                .collect(),
+            placements,
+        };
+
+        let min_rest = TimeDelta::minutes(self.inner.min_rest_minutes as i64);
+
+        let mut res = SchedulePlan::default();
+        for bewerb in self.bewerbs.iter() {
+            let candidates = Self::candidate_slots(bewerb, &slots);
+
+            for round_id in bewerb.get_round_ids() {
+                let earliest = self.previous_round_end(bewerb, round_id, &state);
+                let required = bewerb.get_required_attributes(round_id);
+
+                for group_id in bewerb.get_groups_of_round(round_id) {
+                    if state.get(&group_id).is_some() {
+                        continue;
+                    }
+
+                    let Some(earliest) = earliest else {
+                        res.unplaced.push(group_id);
+                        continue;
+                    };
+
+                    let Some(group) = bewerb.get_group_by_id(&group_id) else {
+                        continue;
+                    };
+                    let fencers: Vec<u32> =
+                        group.get_fencers().iter().map(|x| x.get_id()).collect();
+                    let minutes = self.get_group_minutes(&group_id);
+
+                    let placement = candidates
+                        .iter()
+                        .filter(|slot| earliest.is_none_or(|x| x <= slot.start))
+                        .filter(|slot| self.piste_has_attributes(slot.piste, &required))
+                        .filter_map(|slot| self.get_slot_run(&slot.arena, minutes))
+                        .filter(|run| {
+                            run.iter().all(|x| {
+                                !state.taken.contains(&x.arena)
+                                    && self
+                                        .get_arena(&x.arena)
+                                        .is_some_and(|a| a.allows(&group_id))
+                            })
+                        })
+                        .filter_map(|run| Placement::new(group_id.clone(), &run, fencers.clone()))
+                        .filter(|x| self.get_unavailable_fencers(&fencers, &x.slot).is_empty())
+                        .find(|x| !state.has_fencer_conflict(&x.slot, &fencers, min_rest));
+
+                    let Some(placement) = placement else {
+                        res.unplaced.push(group_id);
+                        continue;
+                    };
+
+                    res.assignments
+                        .push((group_id.clone(), placement.slot.arena.clone()));
+                    state.add(placement);
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Applies all assignments of `plan` or, if one of them does not fit
+    /// anymore, none of them.
+    pub fn apply_plan(&mut self, plan: &SchedulePlan) -> Result<(), Error> {
+        let mut groups = HashSet::new();
+        let mut arenas = HashSet::new();
+
+        for (group_id, arena_id) in plan.assignments.iter() {
+            let Some(group) = self.get_group_by_id(group_id) else {
+                return Err(Error::InvalidInput(format!(
+                    "Ivalid group_id {:?}",
+                    group_id
+                )));
+            };
+            let Some(arena) = self.get_arena_by_id(arena_id) else {
+                return Err(Error::InvalidInput(format!(
+                    "Ivalid arena_id {:?}",
+                    arena_id
+                )));
+            };
+
+            if group.get_arena().is_some() || !groups.insert(group_id.clone()) {
+                return Err(Error::InvalidInput(format!(
+                    "group {:?} is already scheduled",
+                    group_id
+                )));
+            }
+            if !arena.is_free() {
+                return Err(Error::InvalidInput(format!(
+                    "arena {:?} is already taken",
+                    arena_id
+                )));
+            }
+
+            let minutes = self.get_group_minutes(group_id);
+            let Some(run) = self.get_slot_run(arena_id, minutes) else {
+                return Err(Error::InvalidInput(format!(
+                    "group {:?} does not fit into the day after {:?}",
+                    group_id, arena_id
+                )));
+            };
+            for slot in run {
+                let usable = self
+                    .get_arena(&slot.arena)
+                    .is_some_and(|x| x.is_free() && x.allows(group_id));
+                if !usable || !arenas.insert(slot.arena.clone()) {
+                    return Err(Error::InvalidInput(format!(
+                        "arena {:?} is already taken",
+                        slot.arena
+                    )));
+                }
+            }
+        }
+
+        self.transaction(|next| {
+            for (group_id, arena_id) in plan.assignments.iter() {
+                next.add_group_to_arena(group_id, arena_id)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::group::GroupId;
+    use crate::test_support::{add_bewerb, groups, tournament};
+    use crate::validation::ScheduleWarning;
+
+    #[test]
+    fn planned_schedule_leaves_fencers_their_rest() {
+        let mut t = tournament(8, 2);
+        t.inner.round_gap_minutes = 0;
+        t.inner.min_rest_minutes = 90;
+        let bewerb_id = add_bewerb(&mut t, "Degen", 2, 1, 4);
+
+        let plan = t.plan_schedule();
+        assert!(plan.unplaced.is_empty());
+        t.apply_plan(&plan).unwrap();
+
+        let warnings = t.validate_schedule();
+        assert!(!warnings
+            .iter()
+            .any(|x| matches!(x, ScheduleWarning::FencerShortRest { .. })));
+
+        let rounds = groups(&t, bewerb_id);
+        let placements = t.get_placements();
+        let slot = |group: &GroupId| &placements.iter().find(|x| x.group == *group).unwrap().slot;
+        let (first, second) = (slot(&rounds[0]), slot(&rounds[1]));
+        assert!((second.start - first.end).num_minutes() >= 90);
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+
+use crate::error::Error;
+use crate::tournament_core::Tournament;
+
+/// Holds the current version of the tournament.
+///
+/// Readers get an immutable snapshot and never wait for writers. Writers
+/// are serialized, work on a private copy of the current version and
+/// publish it as the new version once they succeeded.
+#[derive(Debug, Default)]
+pub struct TournamentStore {
+    current: RwLock<Option<Arc<Tournament>>>,
+    writer: Mutex<()>,
+}
+
+impl TournamentStore {
+    pub fn new(tournament: Option<Tournament>) -> Self {
+        Self {
+            current: RwLock::new(tournament.map(Arc::new)),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current version, `None` if no tournament is loaded.
+    pub fn snapshot(&self) -> Option<Arc<Tournament>> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Replaces the current version, e.g. after loading from a file.
+    pub async fn replace(&self, tournament: Tournament) {
+        let _writer = self.writer.lock().await;
+        *self.current.write().unwrap() = Some(Arc::new(tournament));
+    }
+
+    /// Applies `f` to a copy of the current version and publishes the copy
+    /// if `f` returns `Ok` and, when enabled, the integrity check passes. On
+    /// error the current version stays untouched.
+    ///
+    /// Returns `None` if no tournament is loaded.
+    pub async fn write<T>(
+        &self,
+        f: impl FnOnce(&mut Tournament) -> Result<T, Error>,
+    ) -> Option<Result<T, Error>> {
+        let _writer = self.writer.lock().await;
+        let mut next = self.snapshot()?.deep_clone();
+        next.in_write = true;
+
+        let res = f(&mut next).and_then(|x| next.verify_after_mutation().map(|_| x));
+        next.in_write = false;
+        if res.is_ok() {
+            *self.current.write().unwrap() = Some(Arc::new(next));
+        }
+
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn failed_write_keeps_current_version() {
+        let store = TournamentStore::new(Some(Tournament::new()));
+
+        let res = store
+            .write(|tournament| {
+                tournament.inner.name = "Changed".to_string();
+                Err::<(), _>(Error::InvalidInput("rejected".to_string()))
+            })
+            .await;
+        assert!(matches!(res, Some(Err(Error::InvalidInput(_)))));
+        assert_eq!(store.snapshot().unwrap().inner.name, "");
+
+        let res = store
+            .write(|tournament| {
+                tournament.inner.name = "Changed".to_string();
+                Ok(())
+            })
+            .await;
+        assert!(matches!(res, Some(Ok(()))));
+        let current = store.snapshot().unwrap();
+        assert_eq!(current.inner.name, "Changed");
+        assert!(!current.in_write);
+    }
+
+    #[tokio::test]
+    async fn write_without_tournament_does_nothing() {
+        let store = TournamentStore::new(None);
+        let res = store.write(|_| Ok(())).await;
+        assert!(res.is_none());
+        assert!(store.snapshot().is_none());
+    }
+}
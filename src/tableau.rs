@@ -0,0 +1,254 @@
+use crate::arena_slot::ArenaSlot;
+use crate::bewerb::BewerbId;
+use crate::container::IdAllocator;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct BoutId {
+    pub bewerb_name: String,
+    pub bewerb_id: u32,
+    pub bout_id: u32,
+}
+
+/// Fencers and result of a direct elimination bout.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct BoutState {
+    /// unknown until the bouts before are decided
+    pub fencers: [Option<u32>; 2],
+    pub winner: Option<u32>,
+    /// touches of the fencers in the order of `fencers`
+    #[serde(default)]
+    pub score: [u32; 2],
+    pub actual_start: Option<NaiveDateTime>,
+    pub actual_end: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Default)]
+pub struct Bout {
+    id: BoutId,
+    /// fencers in the table, 64 for the T64
+    pub table: u32,
+    /// counted from 0, the winner moves on to `position / 2` of the next table
+    pub position: u32,
+    state: Mutex<BoutState>,
+    /// consecutive arena slots of one piste, in chronological order
+    arena_slots: Mutex<Vec<Arc<ArenaSlot>>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct BoutSaveable {
+    id: BoutId,
+    table: u32,
+    position: u32,
+    state: BoutState,
+}
+
+impl From<&Bout> for BoutSaveable {
+    fn from(bout: &Bout) -> Self {
+        Self {
+            id: bout.id.clone(),
+            table: bout.table,
+            position: bout.position,
+            state: bout.get_state(),
+        }
+    }
+}
+
+impl Bout {
+    pub fn from_saveable(bout: &BoutSaveable) -> Self {
+        Self {
+            id: bout.id.clone(),
+            table: bout.table,
+            position: bout.position,
+            state: Mutex::new(bout.state.clone()),
+            arena_slots: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn id(&self) -> BoutId {
+        self.id.clone()
+    }
+
+    /// Name of the table, e.g. "T64".
+    pub fn table_name(&self) -> String {
+        format!("T{}", self.table)
+    }
+
+    pub fn get_state(&self) -> BoutState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn set_state(&self, state: BoutState) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    pub fn get_fencers(&self) -> Vec<u32> {
+        self.state
+            .lock()
+            .unwrap()
+            .fencers
+            .iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Both fencers are known and the bout is not decided yet.
+    pub fn is_ready(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.fencers.iter().all(|x| x.is_some()) && state.winner.is_none()
+    }
+
+    /// The arena slot the bout starts in.
+    pub fn get_arena(&self) -> Option<Arc<ArenaSlot>> {
+        self.arena_slots.lock().unwrap().first().cloned()
+    }
+
+    pub fn get_arenas(&self) -> Vec<Arc<ArenaSlot>> {
+        self.arena_slots.lock().unwrap().clone()
+    }
+
+    pub fn set_arenas(&self, slots: Vec<Arc<ArenaSlot>>) {
+        *self.arena_slots.lock().unwrap() = slots;
+    }
+
+    pub fn add_arena(&self, slot: Arc<ArenaSlot>) {
+        self.arena_slots.lock().unwrap().push(slot);
+    }
+
+    pub fn remove_arena(&self, slot: &Arc<ArenaSlot>) {
+        self.arena_slots
+            .lock()
+            .unwrap()
+            .retain(|x| !Arc::ptr_eq(x, slot));
+    }
+
+    pub fn add_to_arenaslot(bout: Arc<Bout>, arena: Arc<ArenaSlot>) {
+        if let Some(old_group) = arena.get_group() {
+            old_group.remove_arena(&arena);
+        }
+        if let Some(old_bout) = arena.get_bout() {
+            old_bout.remove_arena(&arena);
+        }
+
+        arena.set_bout(Some(bout.clone()));
+        bout.add_arena(arena);
+    }
+}
+
+/// Seeds in the order they are put into the first table, pairing the
+/// best with the worst seed and keeping the top seeds apart.
+fn seed_order(size: u32) -> Vec<u32> {
+    let mut res = vec![1];
+    while (res.len() as u32) < size {
+        let n = res.len() as u32 * 2;
+        res = res.iter().flat_map(|x| [*x, n + 1 - x]).collect();
+    }
+    res
+}
+
+/// The direct elimination of a bewerb, all tables from the first one down
+/// to the final.
+#[derive(Debug, Default)]
+pub struct Tableau {
+    /// ordered by table from the largest, then by position
+    bouts: Vec<Arc<Bout>>,
+}
+
+impl From<&Tableau> for Vec<BoutSaveable> {
+    fn from(tableau: &Tableau) -> Self {
+        tableau.bouts.iter().map(|x| x.as_ref().into()).collect()
+    }
+}
+
+impl Tableau {
+    pub fn from_saveable(bouts: &[BoutSaveable]) -> Self {
+        let mut bouts: Vec<Arc<Bout>> = bouts
+            .iter()
+            .map(|x| Arc::new(Bout::from_saveable(x)))
+            .collect();
+        bouts.sort_by_key(|x| (Reverse(x.table), x.position));
+        Self { bouts }
+    }
+
+    /// Creates all bouts for the fencers in order of their seeding. The
+    /// table is filled up to a power of two with byes, which are decided
+    /// right away.
+    pub fn new(ids: &mut IdAllocator, bewerb_id: &BewerbId, seeding: &[u32]) -> Self {
+        let size = (seeding.len() as u32).next_power_of_two().max(2);
+        let order = seed_order(size);
+        let mut res = Self::default();
+
+        let mut table = size;
+        while table >= 2 {
+            for position in 0..table / 2 {
+                let mut state = BoutState::default();
+                if table == size {
+                    let seed = |x: u32| seeding.get(x as usize - 1).cloned();
+                    state.fencers = [
+                        seed(order[position as usize * 2]),
+                        seed(order[position as usize * 2 + 1]),
+                    ];
+                }
+
+                res.bouts.push(Arc::new(Bout {
+                    id: BoutId {
+                        bewerb_name: bewerb_id.bewerb_name.clone(),
+                        bewerb_id: bewerb_id.bewerb_id,
+                        bout_id: ids.next(),
+                    },
+                    table,
+                    position,
+                    state: Mutex::new(state),
+                    arena_slots: Mutex::new(Vec::new()),
+                }));
+            }
+            table /= 2;
+        }
+
+        let byes: Vec<(Arc<Bout>, u32)> = res
+            .bouts
+            .iter()
+            .filter(|x| x.table == size)
+            .filter_map(|x| match x.get_state().fencers {
+                [Some(fencer), None] | [None, Some(fencer)] => Some((x.clone(), fencer)),
+                _ => None,
+            })
+            .collect();
+        for (bout, fencer) in byes {
+            res.set_winner(&bout, fencer);
+        }
+
+        res
+    }
+
+    pub fn bouts(&self) -> &[Arc<Bout>] {
+        &self.bouts
+    }
+
+    pub fn get_bout(&self, id: &BoutId) -> Option<Arc<Bout>> {
+        self.bouts
+            .iter()
+            .find(|x| x.id.bout_id == id.bout_id)
+            .cloned()
+    }
+
+    /// The bout the winner of `bout` fences next, `None` after the final.
+    pub fn next_bout(&self, bout: &Bout) -> Option<Arc<Bout>> {
+        self.bouts
+            .iter()
+            .find(|x| x.table == bout.table / 2 && x.position == bout.position / 2)
+            .cloned()
+    }
+
+    /// Decides the bout and moves the winner on to the next bout.
+    pub fn set_winner(&self, bout: &Bout, fencer: u32) {
+        bout.state.lock().unwrap().winner = Some(fencer);
+        if let Some(next) = self.next_bout(bout) {
+            next.state.lock().unwrap().fencers[bout.position as usize % 2] = Some(fencer);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Small tournaments for the unit tests.
+
+use chrono::NaiveDate;
+
+use crate::arena_slot::ArenaSlotId;
+use crate::container::HasId;
+use crate::fencer::Fencer;
+use crate::group::{Group, GroupId};
+use crate::time_zone::date_to_timestamp;
+use crate::tournament::SimpleDay;
+use crate::tournament_core::Tournament;
+
+pub fn date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 3, 16).unwrap()
+}
+
+/// A tournament with one day of `n_ts` timeslots of 60 minutes from 09:00
+/// and `n_kp` pistes.
+pub fn tournament(n_ts: u32, n_kp: u32) -> Tournament {
+    let mut res = Tournament::new();
+    res.add_day(SimpleDay {
+        id: 0,
+        date: Some(date_to_timestamp(date(), res.inner.time_zone)),
+        number_time_slots: n_ts,
+        number_arenas: n_kp,
+    })
+    .unwrap();
+    res
+}
+
+/// Adds a bewerb and `fencers_per_group` new fencers to each group of its
+/// first round, the same fencers go on to the groups of the later rounds.
+/// Returns the id of the bewerb.
+pub fn add_bewerb(
+    tournament: &mut Tournament,
+    name: &str,
+    n_rounds: u32,
+    n_groups: u32,
+    fencers_per_group: u32,
+) -> u32 {
+    tournament.add_bewerb(name.to_string(), n_rounds, n_groups);
+    let bewerb = tournament
+        .bewerbs
+        .iter()
+        .find(|x| x.name() == name)
+        .unwrap();
+    let bewerb_id = bewerb.id().clone();
+
+    let mut fencers = Vec::new();
+    for _ in 0..n_groups * fencers_per_group {
+        let id = tournament.inner.ids.next();
+        let fencer = Fencer::new(id, format!("Fencer {}", id), vec![bewerb_id.clone()]);
+        tournament.fencers.insert(fencer);
+        fencers.push(tournament.fencers.get(id).unwrap());
+    }
+
+    for round_id in bewerb.get_round_ids() {
+        for (i, group_id) in bewerb.get_groups_of_round(round_id).iter().enumerate() {
+            let group = bewerb.get_group_by_id(group_id).unwrap();
+            let members = fencers.iter().skip(i).step_by(n_groups as usize);
+            for fencer in members {
+                Group::add_fencer_to_group(group.clone(), fencer.clone());
+            }
+        }
+    }
+    bewerb_id.bewerb_id
+}
+
+/// Groups of the bewerb round after round.
+pub fn groups(tournament: &Tournament, bewerb_id: u32) -> Vec<GroupId> {
+    tournament.bewerbs.get(bewerb_id).unwrap().get_all_groups()
+}
+
+/// The arena slot of the `timeslot`th timeslot and `piste` of the first day.
+pub fn slot(tournament: &Tournament, timeslot: usize, piste: u32) -> ArenaSlotId {
+    let day = tournament.days.iter().next().unwrap();
+    let ts = day.timeslots().nth(timeslot).unwrap();
+    ts.get_all_arenas()
+        .into_iter()
+        .find(|x| x.piste == piste)
+        .map(|x| x.id().clone())
+        .unwrap()
+}
+
+/// Ids of the fencers of the group.
+pub fn fencers(tournament: &Tournament, group: &GroupId) -> Vec<u32> {
+    let group = tournament.get_group(group).unwrap();
+    group.get_fencers().iter().map(|x| x.get_id()).collect()
+}
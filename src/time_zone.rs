@@ -0,0 +1,114 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone};
+use chrono_tz::Tz;
+use prost_types::Timestamp;
+
+use crate::error::Error;
+
+pub fn default_time_zone() -> Tz {
+    Tz::UTC
+}
+
+/// The local date and time at which `ts` happens in `tz`. Fails if `ts` is
+/// outside the range of dates chrono supports.
+pub fn timestamp_to_local(ts: &Timestamp, tz: Tz) -> Result<NaiveDateTime, Error> {
+    let Some(utc) = DateTime::from_timestamp(ts.seconds, 0) else {
+        return Err(Error::InvalidInput(format!(
+            "Ivalid timestamp {:?}",
+            ts.seconds
+        )));
+    };
+    Ok(utc.with_timezone(&tz).naive_local())
+}
+
+/// The instant of the local date and time `local` in `tz`.
+///
+/// On the day clocks are turned back a time exists twice, the earlier one
+/// is used. On the day clocks are turned forward a time might not exist at
+/// all, then the first existing time after it is used.
+pub fn local_to_timestamp(local: NaiveDateTime, tz: Tz) -> Timestamp {
+    let mut time = local;
+    let utc = loop {
+        if let Some(res) = tz.from_local_datetime(&time).earliest() {
+            break res.timestamp();
+        }
+        if time - local > TimeDelta::days(1) {
+            break local.and_utc().timestamp();
+        }
+        time += TimeDelta::minutes(15);
+    };
+
+    Timestamp {
+        seconds: utc,
+        nanos: 0,
+    }
+}
+
+pub fn timestamp_to_date(ts: &Timestamp, tz: Tz) -> Result<NaiveDate, Error> {
+    Ok(timestamp_to_local(ts, tz)?.date())
+}
+
+/// The instant the local date `date` starts in `tz`.
+pub fn date_to_timestamp(date: NaiveDate, tz: Tz) -> Timestamp {
+    local_to_timestamp(date.and_time(NaiveTime::MIN), tz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(date: (i32, u32, u32), time: (u32, u32)) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+            .unwrap()
+            .and_hms_opt(time.0, time.1, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn utc_round_trip() {
+        let time = local((2024, 6, 1), (9, 30));
+        let ts = local_to_timestamp(time, Tz::UTC);
+        assert_eq!(ts.seconds, time.and_utc().timestamp());
+        assert_eq!(timestamp_to_local(&ts, Tz::UTC).unwrap(), time);
+    }
+
+    #[test]
+    fn spring_forward_gap_uses_next_existing_time() {
+        let tz = Tz::Europe__Vienna;
+        let ts = local_to_timestamp(local((2024, 3, 31), (2, 30)), tz);
+        assert_eq!(
+            timestamp_to_local(&ts, tz).unwrap(),
+            local((2024, 3, 31), (3, 0))
+        );
+    }
+
+    #[test]
+    fn autumn_fold_uses_earlier_time() {
+        let tz = Tz::Europe__Vienna;
+        let time = local((2024, 10, 27), (2, 30));
+        let ts = local_to_timestamp(time, tz);
+        assert_eq!(
+            ts.seconds,
+            local((2024, 10, 27), (0, 30)).and_utc().timestamp()
+        );
+        assert_eq!(timestamp_to_local(&ts, tz).unwrap(), time);
+    }
+
+    #[test]
+    fn date_round_trip() {
+        let tz = Tz::America__New_York;
+        let date = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        assert_eq!(
+            timestamp_to_date(&date_to_timestamp(date, tz), tz).unwrap(),
+            date
+        );
+    }
+
+    #[test]
+    fn out_of_range_timestamp_fails() {
+        let ts = Timestamp {
+            seconds: i64::MAX,
+            nanos: 0,
+        };
+        assert!(timestamp_to_date(&ts, Tz::UTC).is_err());
+    }
+}
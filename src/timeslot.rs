@@ -1,11 +1,15 @@
 use crate::arena_slot::{ArenaSlot, ArenaSlotId, ArenaSlotSaveable};
 use crate::bewerb::Bewerb;
-use crate::container::{HasId, UidContainer};
+use crate::container::{HasId, IdAllocator, UidContainer};
 use crate::tournament;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+/// Length of a timeslot in minutes unless it is set explicitly.
+pub const DEFAULT_TIMESLOT_MINUTES: u32 = 60;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct TimeslotId {
     pub day_id: u32,
     pub timeslot_id: u32,
@@ -14,12 +18,23 @@ pub struct TimeslotId {
 #[derive(Debug, Default)]
 pub struct Timeslot {
     pub id: TimeslotId,
-    arenas: Vec<Arc<ArenaSlot>>,
+    pub start: NaiveTime,
+    /// length in minutes
+    pub duration: u32,
+    /// pistes available in this timeslot, those of the day if empty
+    pub enabled_pistes: Vec<u32>,
+    arenas: UidContainer<Arc<ArenaSlot>>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct TimeslotSaveable {
     id: TimeslotId,
+    #[serde(default)]
+    start: Option<NaiveTime>,
+    #[serde(default)]
+    duration: Option<u32>,
+    #[serde(default)]
+    enabled_pistes: Vec<u32>,
     arenas: Vec<ArenaSlotSaveable>,
 }
 
@@ -29,19 +44,24 @@ impl From<&Timeslot> for TimeslotSaveable {
 
         Self {
             id: ts.id.clone(),
+            start: Some(ts.start),
+            duration: Some(ts.duration),
+            enabled_pistes: ts.enabled_pistes.clone(),
             arenas,
         }
     }
 }
 
 impl Timeslot {
+    /// `start` is used for files written before timeslots had times.
     pub fn from_timeslot_saveable(
         ts_saveables: TimeslotSaveable,
+        start: NaiveTime,
         bewerbs: &mut UidContainer<Bewerb>,
     ) -> Self {
-        let mut arenas: Vec<Arc<ArenaSlot>> = Default::default();
+        let mut arenas: UidContainer<Arc<ArenaSlot>> = Default::default();
         for ts_saveable in ts_saveables.arenas.iter() {
-            arenas.push(ArenaSlot::from_arena_slot_saveable(
+            arenas.insert(ArenaSlot::from_arena_slot_saveable(
                 ts_saveable.clone(),
                 bewerbs,
             ));
@@ -49,31 +69,86 @@ impl Timeslot {
 
         Self {
             id: ts_saveables.id,
+            start: ts_saveables.start.unwrap_or(start),
+            duration: ts_saveables.duration.unwrap_or(DEFAULT_TIMESLOT_MINUTES),
+            enabled_pistes: ts_saveables.enabled_pistes,
             arenas,
         }
     }
 
-    pub fn new(id: TimeslotId, n_kp: u32) -> Self {
+    pub fn new(
+        ids: &mut IdAllocator,
+        id: TimeslotId,
+        n_kp: u32,
+        start: NaiveTime,
+        duration: u32,
+    ) -> Self {
         let mut res = Self {
             id,
+            start,
+            duration,
             ..Default::default()
         };
 
-        for i in 0..n_kp {
+        for piste in 0..n_kp {
             let id = ArenaSlotId {
                 day_id: res.id.day_id,
                 timeslot_id: res.id.timeslot_id,
-                arena_slot_id: i,
+                arena_slot_id: ids.next(),
             };
 
-            let arena = ArenaSlot::new(id);
-            res.arenas.push(Arc::new(arena));
+            let arena = ArenaSlot::new(id, piste);
+            res.arenas.insert(Arc::new(arena));
         }
         res
     }
 
+    /// Adds arena slots for missing pistes below `n_kp` and removes those
+    /// of pistes from `n_kp` on.
+    pub fn set_n_kp(&mut self, ids: &mut IdAllocator, n_kp: u32) {
+        let removed: Vec<u32> = self
+            .arenas
+            .iter()
+            .filter(|x| x.piste >= n_kp)
+            .map(|x| x.get_id())
+            .collect();
+        for id in removed {
+            self.arenas.remove(id);
+        }
+
+        for piste in 0..n_kp {
+            if self.arenas.iter().any(|x| x.piste == piste) {
+                continue;
+            }
+
+            let id = ArenaSlotId {
+                day_id: self.id.day_id,
+                timeslot_id: self.id.timeslot_id,
+                arena_slot_id: ids.next(),
+            };
+            self.arenas.insert(Arc::new(ArenaSlot::new(id, piste)));
+        }
+    }
+
     pub fn get_arena(&self, id: &ArenaSlotId) -> Option<Arc<ArenaSlot>> {
-        self.arenas.iter().find(|x| x.id() == id).cloned()
+        self.arenas
+            .get(id.arena_slot_id)
+            .filter(|x| x.id() == id)
+            .cloned()
+    }
+
+    /// The end on `date`, the date of its day, which is the next date if
+    /// the timeslot ends at midnight.
+    pub fn end(&self, date: NaiveDate) -> NaiveDateTime {
+        date.and_time(self.start) + TimeDelta::minutes(self.duration as i64)
+    }
+
+    pub fn contains(&self, date: NaiveDate, time: NaiveDateTime) -> bool {
+        date.and_time(self.start) <= time && time < self.end(date)
+    }
+
+    pub fn get_all_arenas(&self) -> Vec<Arc<ArenaSlot>> {
+        self.arenas.iter().cloned().collect()
     }
 }
 
@@ -89,8 +164,4 @@ impl HasId for Timeslot {
     fn get_id(&self) -> u32 {
         self.id.timeslot_id
     }
-
-    fn set_id(&mut self, id: u32) {
-        self.id.timeslot_id = id;
-    }
 }
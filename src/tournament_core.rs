@@ -1,22 +1,91 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::bewerb::{Bewerb, BewerbSaveable};
-use crate::day::{Day, DaySaveable};
+use crate::bewerb::{Bewerb, BewerbSaveable, Weapon};
+use crate::day::{Day, DayBreak, DaySaveable};
 use crate::error::Error;
 
 use crate::tournament::{DayData, SimpleDay, SimpleFencer};
 
-use crate::arena_slot::{ArenaSlot, ArenaSlotId};
-use crate::container::UidContainer;
-use crate::fencer::{Fencer, Fencers};
+use crate::arena_slot::{ArenaSlot, ArenaSlotId, SlotBlock};
+use crate::container::{HasId, IdAllocator, UidContainer};
+use crate::fencer::{AvailabilityWindow, Fencer, Fencers};
 use crate::group::{Group, GroupId};
+use crate::tableau::{Bout, BoutId};
+use crate::time_zone::default_time_zone;
+use crate::timeslot::TimeslotId;
+use crate::validation::{default_min_rest_minutes, default_round_gap_minutes, ScheduleWarning};
+use crate::venue::{Piste, PisteAttribute};
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TournamentInternal {
     pub name: String,
+    #[serde(default)]
+    pub ids: IdAllocator,
+    /// Dates and times of days and timeslots are local to this time zone.
+    #[serde(default = "default_time_zone")]
+    pub time_zone: Tz,
+    /// rest a fencer should have between two of their groups
+    #[serde(default = "default_min_rest_minutes")]
+    pub min_rest_minutes: u32,
+    /// time for results processing between two rounds of a bewerb
+    #[serde(default = "default_round_gap_minutes")]
+    pub round_gap_minutes: u32,
+    /// reject instead of warn about groups scheduled before their previous round
+    #[serde(default)]
+    pub enforce_round_order: bool,
+    /// named pistes of the venue, pistes without an entry are anonymous
+    #[serde(default)]
+    pub pistes: Vec<Piste>,
+    /// first and last date of the tournament, days have to be within
+    #[serde(default)]
+    pub first_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub last_date: Option<NaiveDate>,
+}
+
+impl Default for TournamentInternal {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            ids: IdAllocator::default(),
+            time_zone: default_time_zone(),
+            min_rest_minutes: default_min_rest_minutes(),
+            round_gap_minutes: default_round_gap_minutes(),
+            enforce_round_order: false,
+            pistes: Vec::new(),
+            first_date: None,
+            last_date: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DeletionImpact {
+    pub groups: Vec<GroupId>,
+    /// elimination bouts that have to be dispatched again
+    pub bouts: Vec<BoutId>,
+    pub arenas: Vec<ArenaSlotId>,
+    pub fencers: Vec<u32>,
+}
+
+impl DeletionImpact {
+    fn add_group(&mut self, group: &Group) {
+        self.groups.push(group.id());
+        for fencer in group.get_fencers() {
+            self.add_fencer(fencer.get_id());
+        }
+    }
+
+    fn add_fencer(&mut self, id: u32) {
+        if !self.fencers.contains(&id) {
+            self.fencers.push(id);
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -25,6 +94,9 @@ pub struct Tournament {
     pub bewerbs: UidContainer<Bewerb>,
     pub days: UidContainer<Day>,
     pub fencers: Fencers,
+    /// set on a copy that is thrown away if the change fails, see
+    /// `transaction`
+    pub(crate) in_write: bool,
 }
 
 impl Tournament {
@@ -80,12 +152,89 @@ impl Tournament {
         let fencers = Fencers::from_json_file(&bewerbs).unwrap_or_default();
         let days = Self::load_days_from_json_file(&mut bewerbs).unwrap_or_default();
 
-        Ok(Tournament {
+        let mut res = Tournament {
             inner,
             bewerbs,
             fencers,
             days,
-        })
+            in_write: false,
+        };
+        res.reserve_loaded_ids();
+        Ok(res)
+    }
+
+    /// Copies the whole object graph, the copy shares no entity with `self`.
+    pub fn deep_clone(&self) -> Tournament {
+        let mut bewerbs: UidContainer<Bewerb> = Default::default();
+        for bewerb in self.bewerbs.iter() {
+            bewerbs.insert(Bewerb::from_saveable(&bewerb.into()));
+        }
+
+        let fencers = Fencers::from((&self.fencers).into(), &bewerbs);
+
+        let mut days: UidContainer<Day> = Default::default();
+        for day in self.days.iter() {
+            days.insert(Day::from_saveable(day.into(), &mut bewerbs));
+        }
+
+        Tournament {
+            inner: self.inner.clone(),
+            bewerbs,
+            days,
+            fencers,
+            in_write: false,
+        }
+    }
+
+    pub(crate) fn all_groups(&self) -> Vec<Arc<Group>> {
+        let mut res = Vec::new();
+        for bewerb in self.bewerbs.iter() {
+            for id in bewerb.get_all_groups() {
+                if let Some(group) = bewerb.get_group_by_id(&id) {
+                    res.push(group);
+                }
+            }
+        }
+        res
+    }
+
+    pub(crate) fn all_bouts(&self) -> Vec<Arc<Bout>> {
+        self.bewerbs
+            .iter()
+            .flat_map(|x| x.get_tableau().bouts().iter().cloned())
+            .collect()
+    }
+
+    pub(crate) fn all_arenas(&self) -> Vec<Arc<ArenaSlot>> {
+        self.days.iter().flat_map(|x| x.get_all_arenas()).collect()
+    }
+
+    fn reserve_loaded_ids(&mut self) {
+        let ids = &mut self.inner.ids;
+        for bewerb in self.bewerbs.iter() {
+            ids.reserve(bewerb.get_id());
+            for round_id in bewerb.get_round_ids() {
+                ids.reserve(round_id);
+            }
+            for group_id in bewerb.get_all_groups() {
+                ids.reserve(group_id.group_id);
+            }
+            for bout in bewerb.get_tableau().bouts() {
+                ids.reserve(bout.id().bout_id);
+            }
+        }
+
+        for day in self.days.iter() {
+            ids.reserve(day.get_id());
+            for arena in day.get_all_arenas() {
+                ids.reserve(arena.id().timeslot_id);
+                ids.reserve(arena.id().arena_slot_id);
+            }
+        }
+
+        for fencer in self.fencers.iter() {
+            ids.reserve(fencer.get_id());
+        }
     }
 
     pub fn to_json_file(&self, path: &Path) -> Result<(), Error> {
@@ -104,37 +253,302 @@ impl Tournament {
         Ok(())
     }
 
-    pub fn add_day(&mut self, day: SimpleDay) {
-        let id = self.days.get_next_id();
-        let day = Day::from(id, day);
-        self.days.push(day);
+    /// Fails if another day than `day_id` is on the date or the date is
+    /// outside the tournament.
+    pub(crate) fn check_day_date(&self, day_id: Option<u32>, date: NaiveDate) -> Result<(), Error> {
+        let outside = self.inner.first_date.is_some_and(|x| date < x)
+            || self.inner.last_date.is_some_and(|x| date > x);
+        if outside {
+            return Err(Error::InvalidInput(format!(
+                "{} is outside the tournament",
+                date
+            )));
+        }
+        if self
+            .days
+            .iter()
+            .any(|x| Some(x.get_id()) != day_id && x.date() == date)
+        {
+            return Err(Error::InvalidInput(format!(
+                "there is already a day on {}",
+                date
+            )));
+        }
+        Ok(())
     }
 
-    pub fn remove_day(&mut self, id: u32) {
+    pub fn add_day(&mut self, day: SimpleDay) -> Result<(), Error> {
+        let day = Day::from(&mut self.inner.ids, day, self.inner.time_zone)?;
+        self.check_day_date(None, day.date())?;
+        self.days.insert(day);
+        Ok(())
+    }
+
+    /// Sets the dates of the tournament. Fails if a day is outside them.
+    pub fn set_date_range(
+        &mut self,
+        first: Option<NaiveDate>,
+        last: Option<NaiveDate>,
+    ) -> Result<(), Error> {
+        if let (Some(first), Some(last)) = (first, last) {
+            if last < first {
+                return Err(Error::InvalidInput(format!(
+                    "tournament ends on {} before it starts",
+                    last
+                )));
+            }
+        }
+
+        let outside = self
+            .days
+            .iter()
+            .find(|x| first.is_some_and(|f| x.date() < f) || last.is_some_and(|l| x.date() > l));
+        if let Some(day) = outside {
+            return Err(Error::InvalidInput(format!(
+                "day {:?} on {} is outside the tournament",
+                day.get_id(),
+                day.date()
+            )));
+        }
+
+        self.inner.first_date = first;
+        self.inner.last_date = last;
+        Ok(())
+    }
+
+    pub fn preview_remove_day(&self, id: u32) -> Result<DeletionImpact, Error> {
+        let Some(day) = self.days.get(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid day_id {:?}", id)));
+        };
+
+        let mut res = DeletionImpact::default();
+        for arena in day.get_all_arenas() {
+            if let Some(bout) = arena.get_bout() {
+                res.arenas.push(arena.id().clone());
+                if !res.bouts.contains(&bout.id()) {
+                    res.bouts.push(bout.id());
+                }
+                continue;
+            }
+            let Some(group) = arena.get_group() else {
+                continue;
+            };
+
+            res.arenas.push(arena.id().clone());
+            if !res.groups.contains(&group.id()) {
+                res.add_group(&group);
+            }
+        }
+
+        Ok(res)
+    }
+
+    pub fn remove_day(&mut self, id: u32) -> Result<DeletionImpact, Error> {
+        let res = self.preview_remove_day(id)?;
+        for arena_id in &res.arenas {
+            self.freeup_arena(arena_id)?;
+        }
+
         self.days.remove(id);
+        Ok(res)
+    }
+
+    /// Changes date, number of timeslots and number of pistes of the day.
+    /// Groups keep their arena slots if all of them still exist, the
+    /// others are freed and returned. Elimination bouts losing a slot are
+    /// freed to be dispatched again. Nothing changes if added timeslots would
+    /// end after closing.
+    pub fn update_day(
+        &mut self,
+        id: u32,
+        date: NaiveDate,
+        n_ts: u32,
+        n_kp: u32,
+    ) -> Result<Vec<GroupId>, Error> {
+        let Some(day) = self.days.get(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid day_id {:?}", id)));
+        };
+        self.check_day_date(Some(id), date)?;
+
+        let outside = day.get_arenas_outside(n_ts, n_kp);
+        let day = self.days.get_mut(id).unwrap();
+        day.reshape(&mut self.inner.ids, n_ts, n_kp)?;
+        day.set_date(date);
+
+        let mut displaced = Vec::new();
+        for arena in outside {
+            if let Some(bout) = arena.get_bout() {
+                Self::freeup_bout(&bout);
+            }
+            if let Some(group) = arena.get_group() {
+                if !displaced.contains(&group.id()) {
+                    displaced.push(group.id());
+                }
+            }
+        }
+        for group_id in displaced.iter() {
+            self.freeup_group(group_id)?;
+        }
+
+        Ok(displaced)
+    }
+
+    /// Sets the time zone by its IANA name, e.g. "Europe/Vienna".
+    pub fn set_time_zone(&mut self, name: &str) -> Result<(), Error> {
+        let Ok(tz) = name.parse::<Tz>() else {
+            return Err(Error::InvalidInput(format!("Ivalid time zone {:?}", name)));
+        };
+
+        self.inner.time_zone = tz;
+        Ok(())
+    }
+
+    fn get_day_mut(&mut self, id: u32) -> Result<&mut Day, Error> {
+        self.days
+            .get_mut(id)
+            .ok_or_else(|| Error::InvalidInput(format!("Ivalid day_id {:?}", id)))
+    }
+
+    pub fn set_day_start(&mut self, day_id: u32, start: NaiveTime) -> Result<(), Error> {
+        self.get_day_mut(day_id)?.set_start(start)
+    }
+
+    /// Sets opening and closing time and breaks of the day, see
+    /// `Day::set_hours`.
+    pub fn set_day_hours(
+        &mut self,
+        day_id: u32,
+        open: NaiveTime,
+        close: Option<NaiveTime>,
+        breaks: Vec<DayBreak>,
+    ) -> Result<(), Error> {
+        self.get_day_mut(day_id)?.set_hours(open, close, breaks)
+    }
+
+    pub fn set_timeslot_time(
+        &mut self,
+        id: &TimeslotId,
+        start: NaiveTime,
+        duration: u32,
+    ) -> Result<(), Error> {
+        self.get_day_mut(id.day_id)?
+            .set_timeslot_time(id.timeslot_id, start, duration)
+    }
+
+    pub fn add_break(
+        &mut self,
+        id: &TimeslotId,
+        minutes: u32,
+        reason: String,
+    ) -> Result<(), Error> {
+        self.get_day_mut(id.day_id)?
+            .add_break(id.timeslot_id, minutes, reason)
+    }
+
+    /// All arena slots whose timeslot is running at `time`.
+    pub fn get_arenas_at(&self, time: NaiveDateTime) -> Vec<Arc<ArenaSlot>> {
+        self.days
+            .iter()
+            .filter(|x| x.date() == time.date())
+            .filter_map(|x| x.get_timeslot_at(time))
+            .flat_map(|x| x.get_all_arenas())
+            .collect()
     }
 
     pub fn get_simple_days(&self) -> Vec<SimpleDay> {
-        self.days.iter().map(|e| e.into()).collect()
+        self.days
+            .iter()
+            .map(|e| e.to_simple_day(self.inner.time_zone))
+            .collect()
     }
 
     pub fn add_bewerb(&mut self, name: String, n_rounds: u32, n_groups: u32) {
-        let id = self.bewerbs.get_next_id();
-        let bewerb = Bewerb::new(id, name, n_rounds, n_groups);
+        let bewerb = Bewerb::new(&mut self.inner.ids, name, n_rounds, n_groups);
         self.bewerbs.insert(bewerb);
     }
 
-    pub fn remove_bewerb(&mut self, id: u32) {
+    pub fn preview_remove_bewerb(&self, id: u32) -> Result<DeletionImpact, Error> {
         let Some(bewerb) = self.bewerbs.get(id) else {
-            return;
+            return Err(Error::InvalidInput(format!("Ivalid bewerb_id {:?}", id)));
         };
 
-        let groups = bewerb.get_all_groups();
-        for group in groups {
-            let _ = self.freeup_group(&group);
+        let mut res = DeletionImpact::default();
+        for group_id in bewerb.get_all_groups() {
+            let Some(group) = bewerb.get_group_by_id(&group_id) else {
+                continue;
+            };
+
+            for arena in group.get_arenas() {
+                res.arenas.push(arena.id().clone());
+            }
+            res.add_group(&group);
+        }
+
+        for bout in bewerb.get_tableau().bouts() {
+            let arenas = bout.get_arenas();
+            if arenas.is_empty() {
+                continue;
+            }
+
+            res.arenas.extend(arenas.iter().map(|x| x.id().clone()));
+            res.bouts.push(bout.id());
+        }
+
+        for fencer in self.fencers.iter().filter(|x| x.is_in_bewerb(id)) {
+            res.add_fencer(fencer.get_id());
+        }
+
+        Ok(res)
+    }
+
+    pub fn remove_bewerb(&mut self, id: u32) -> Result<DeletionImpact, Error> {
+        let res = self.preview_remove_bewerb(id)?;
+        for group_id in &res.groups {
+            self.freeup_group(group_id)?;
+        }
+        if let Some(bewerb) = self.bewerbs.get(id) {
+            for bout in bewerb.get_tableau().bouts() {
+                Self::freeup_bout(bout);
+            }
+        }
+
+        for fencer in self.fencers.iter() {
+            fencer.remove_bewerb(id);
         }
 
         self.bewerbs.remove(id);
+        Ok(res)
+    }
+
+    fn get_bewerb_mut(&mut self, id: u32) -> Result<&mut Bewerb, Error> {
+        self.bewerbs
+            .get_mut(id)
+            .ok_or_else(|| Error::InvalidInput(format!("Ivalid bewerb_id {:?}", id)))
+    }
+
+    pub fn set_bewerb_preferred_days(&mut self, id: u32, days: Vec<u32>) -> Result<(), Error> {
+        self.get_bewerb_mut(id)?.preferred_days = days;
+        Ok(())
+    }
+
+    pub fn set_bewerb_allowed_pistes(&mut self, id: u32, pistes: Vec<u32>) -> Result<(), Error> {
+        self.get_bewerb_mut(id)?.allowed_pistes = pistes;
+        Ok(())
+    }
+
+    pub fn set_round_required_attributes(
+        &mut self,
+        bewerb_id: u32,
+        round_id: u32,
+        attributes: Vec<PisteAttribute>,
+    ) -> Result<(), Error> {
+        self.get_bewerb_mut(bewerb_id)?
+            .set_required_attributes(round_id, attributes)
+    }
+
+    pub fn set_bewerb_weapon(&mut self, id: u32, weapon: Weapon) -> Result<(), Error> {
+        self.get_bewerb_mut(id)?.weapon = weapon;
+        Ok(())
     }
 
     pub fn get_bewerbs(&self) -> Vec<&Bewerb> {
@@ -164,6 +578,10 @@ impl Tournament {
         Self::get_group_by_id_internal(&mut self.bewerbs, id)
     }
 
+    pub fn get_group(&self, id: &GroupId) -> Option<Arc<Group>> {
+        self.bewerbs.get(id.bewerb_id)?.get_group_by_id(id)
+    }
+
     fn get_arena_by_id_internal(
         days: &mut UidContainer<Day>,
         id: &ArenaSlotId,
@@ -176,56 +594,186 @@ impl Tournament {
         Self::get_arena_by_id_internal(&mut self.days, id)
     }
 
+    pub fn get_arena(&self, id: &ArenaSlotId) -> Option<Arc<ArenaSlot>> {
+        self.days
+            .get(id.day_id)?
+            .get_timeslot(id.timeslot_id)?
+            .get_arena(id)
+    }
+
+    /// Blocks or reserves the arena slot. A group in it that the block
+    /// does not allow has to be freed first.
+    pub fn block_arena(&mut self, id: &ArenaSlotId, block: SlotBlock) -> Result<(), Error> {
+        let Some(arena) = self.get_arena(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid arena_id {:?}", id)));
+        };
+
+        if let Some(group) = arena.get_group() {
+            if !block.allows(&group.id()) {
+                return Err(Error::InvalidInput(format!(
+                    "arena {:?} is used by {:?}",
+                    id,
+                    group.id()
+                )));
+            }
+        }
+        if let Some(bout) = arena.get_bout() {
+            if !block.allows_bout(&bout.id()) {
+                return Err(Error::InvalidInput(format!(
+                    "arena {:?} is used by {:?}",
+                    id,
+                    bout.id()
+                )));
+            }
+        }
+
+        arena.set_block(Some(block));
+        Ok(())
+    }
+
+    pub fn unblock_arena(&mut self, id: &ArenaSlotId) -> Result<(), Error> {
+        let Some(arena) = self.get_arena(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid arena_id {:?}", id)));
+        };
+
+        arena.set_block(None);
+        Ok(())
+    }
+
     pub fn freeup_group(&mut self, id: &GroupId) -> Result<(), Error> {
         let Some(group) = Self::get_group_by_id_internal(&mut self.bewerbs, id) else {
             return Err(Error::InvalidInput(format!("Ivalid group_id {:?}", id)));
         };
 
-        let Some(arena) = group.get_arena() else {
-            return Ok(());
-        };
-
-        arena.set_group(None);
+        for arena in group.get_arenas() {
+            arena.set_group(None);
+        }
         group.set_arena(None);
 
         Ok(())
     }
 
+    pub(crate) fn freeup_bout(bout: &Bout) {
+        for arena in bout.get_arenas() {
+            arena.set_bout(None);
+        }
+        bout.set_arenas(Vec::new());
+    }
+
+    /// Frees the group or bout in the arena slot from all of its arena
+    /// slots.
     fn freeup_arena(&mut self, id: &ArenaSlotId) -> Result<(), Error> {
         let Some(arena) = Self::get_arena_by_id_internal(&mut self.days, id) else {
             return Err(Error::InvalidInput(format!("Ivalid arena_id {:?}", id)));
         };
 
+        if let Some(bout) = arena.get_bout() {
+            Self::freeup_bout(&bout);
+            return Ok(());
+        }
         let Some(group) = arena.get_group() else {
             return Ok(());
         };
 
+        for arena in group.get_arenas() {
+            arena.set_group(None);
+        }
         group.set_arena(None);
         arena.set_group(None);
 
         Ok(())
     }
 
+    /// Puts the group into the arena slot and, if it takes longer than the
+    /// timeslot, into the slots of the same piste in the following
+    /// timeslots. Fails if one of them is used by another group or a
+    /// bout, or if the piste lacks an attribute the round requires. Returns
+    /// the warnings that concern the group afterwards.
     pub fn add_group_to_arena(
         &mut self,
         group_id: &GroupId,
         arena_id: &ArenaSlotId,
-    ) -> Result<(), Error> {
-        self.freeup_arena(arena_id)?;
-        self.freeup_group(group_id)?;
-
-        let Some(arena) = Self::get_arena_by_id_internal(&mut self.days, arena_id) else {
+    ) -> Result<Vec<ScheduleWarning>, Error> {
+        if self.get_arena_by_id(arena_id).is_none() {
             return Err(Error::InvalidInput("Ivalid arena_id".to_string()));
-        };
+        }
+        if !self.is_arena_enabled(arena_id) {
+            return Err(Error::InvalidInput(format!(
+                "piste of arena {:?} is not available",
+                arena_id
+            )));
+        }
 
         let Some(group) = Self::get_group_by_id_internal(&mut self.bewerbs, group_id) else {
             return Err(Error::InvalidInput("Ivalid group_id".to_string()));
         };
 
-        arena.set_group(Some(group.clone()));
-        group.set_arena(Some(arena));
+        let minutes = self.get_group_minutes(group_id);
+        let Some(run) = self.get_slot_run(arena_id, minutes) else {
+            return Err(Error::InvalidInput(format!(
+                "group {:?} does not fit into the day after {:?}",
+                group_id, arena_id
+            )));
+        };
 
-        Ok(())
+        let required = self
+            .bewerbs
+            .get(group_id.bewerb_id)
+            .map(|x| x.get_required_attributes(group_id.round_id))
+            .unwrap_or_default();
+        let piste = run.first().map(|x| x.piste).unwrap_or_default();
+        if !self.piste_has_attributes(piste, &required) {
+            return Err(Error::InvalidInput(format!(
+                "piste {} lacks an attribute of {:?}",
+                self.piste_name(piste),
+                required
+            )));
+        }
+
+        for slot in run.iter() {
+            let Some(arena) = self.get_arena(&slot.arena) else {
+                continue;
+            };
+            if let Some(other) = arena.get_group().filter(|x| !Arc::ptr_eq(x, &group)) {
+                return Err(Error::InvalidInput(format!(
+                    "arena {:?} is used by {:?}",
+                    slot.arena,
+                    other.id()
+                )));
+            }
+            if let Some(bout) = arena.get_bout() {
+                return Err(Error::InvalidInput(format!(
+                    "arena {:?} is used by {:?}",
+                    slot.arena,
+                    bout.id()
+                )));
+            }
+
+            let Some(block) = arena.get_block() else {
+                continue;
+            };
+            if !block.allows(group_id) {
+                return Err(Error::InvalidInput(format!(
+                    "arena {:?} is blocked: {}",
+                    slot.arena,
+                    block.reason()
+                )));
+            }
+        }
+
+        self.check_round_order(group_id, arena_id)?;
+        self.freeup_group(group_id)?;
+
+        let mut arenas = Vec::new();
+        for slot in run.iter() {
+            if let Some(arena) = self.get_arena_by_id(&slot.arena) {
+                arena.set_group(Some(group.clone()));
+                arenas.push(arena);
+            }
+        }
+        group.set_arenas(arenas);
+
+        Ok(self.get_group_warnings(group_id))
     }
 
     pub fn get_day_data(&self, id: u32) -> Result<DayData, Error> {
@@ -236,20 +784,92 @@ impl Tournament {
         Ok(day.into())
     }
 
-    pub fn get_all_fencers(&mut self) -> Result<Vec<SimpleFencer>, Error> {
+    pub fn get_all_fencers(&self) -> Result<Vec<SimpleFencer>, Error> {
         Ok(self.fencers.iter().map(|x| x.as_ref().into()).collect())
     }
 
+    pub fn set_fencer_availability(
+        &mut self,
+        id: u32,
+        windows: Vec<AvailabilityWindow>,
+    ) -> Result<(), Error> {
+        let Some(fencer) = self.fencers.get(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid fencer_id {:?}", id)));
+        };
+        AvailabilityWindow::check_all(&windows)?;
+        fencer.set_availability(windows);
+        Ok(())
+    }
+
+    pub fn set_fencer_club(&mut self, id: u32, club: Option<String>) -> Result<(), Error> {
+        let Some(fencer) = self.fencers.get(id) else {
+            return Err(Error::InvalidInput(format!("Ivalid fencer_id {:?}", id)));
+        };
+        fencer.set_club(club);
+        Ok(())
+    }
+
+    /// Sets the availability of every fencer of the club, e.g. when the
+    /// club travels together. Returns the number of fencers changed.
+    pub fn set_club_availability(
+        &mut self,
+        club: &str,
+        windows: Vec<AvailabilityWindow>,
+    ) -> Result<usize, Error> {
+        let members: Vec<_> = self
+            .fencers
+            .iter()
+            .filter(|x| x.get_club().as_deref() == Some(club))
+            .collect();
+        if members.is_empty() {
+            return Err(Error::InvalidInput(format!("Ivalid club {:?}", club)));
+        }
+        AvailabilityWindow::check_all(&windows)?;
+
+        for fencer in members.iter() {
+            fencer.set_availability(windows.clone());
+        }
+        Ok(members.len())
+    }
+
     pub fn update_fencers(&mut self, fencers: Vec<SimpleFencer>) {
         for fencer in fencers {
             if let Some(item) = self.fencers.iter_mut().find(|x| x.is_same(&fencer)) {
                 item.update(fencer.clone());
             } else {
                 let new_fencer = Fencer::new(
+                    self.inner.ids.next(),
                     fencer.name.to_owned(),
                     fencer.bewerbs.iter().map(|x| x.into()).collect(),
                 );
-                self.fencers.push(new_fencer);
+                self.fencers.insert(new_fencer);
+            }
+        }
+    }
+}
+
+impl Drop for Tournament {
+    /// Groups, bouts, arena slots and fencers hold `Arc`s of each other, the
+    /// links are cut so the entities are freed together with the tournament.
+    fn drop(&mut self) {
+        for group in self.all_groups() {
+            group.set_arena(None);
+            for fencer in group.get_fencers() {
+                group.remove_fencer(fencer.get_id());
+            }
+        }
+
+        for bout in self.all_bouts() {
+            bout.set_arenas(Vec::new());
+        }
+
+        for arena in self.all_arenas() {
+            arena.set_group(None);
+        }
+
+        for fencer in self.fencers.iter() {
+            for group in fencer.get_groups() {
+                fencer.remove_group(&group);
             }
         }
     }
@@ -7,60 +7,75 @@ use crate::tournament::{
     RemoveBewerbResponse, RemoveDayRequest, RemoveDayResponse, SaveRequest, SaveResponse,
 };
 
+use crate::store::TournamentStore;
 use crate::tournament_core::Tournament;
 
 use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tonic::metadata::MetadataValue;
+
+/// Metadata key of the scheduling conflicts `add_group_to_arena` reports.
+pub const SCHEDULE_WARNING_KEY: &str = "schedule-warning-bin";
 
 #[derive(Debug)]
 pub struct TournamentService {
-    tournament: Arc<Mutex<Option<Tournament>>>,
+    tournament: Arc<TournamentStore>,
 }
 
 impl TournamentService {
-    pub fn new(tournament: Arc<Mutex<Option<Tournament>>>) -> Self {
+    pub fn new(tournament: Arc<TournamentStore>) -> Self {
         Self { tournament }
     }
 }
 
+fn not_loaded() -> tonic::Status {
+    tonic::Status::new(tonic::Code::Internal, "not loaded jet".to_string())
+}
+
 #[tonic::async_trait]
 impl tournament_server::Tournament for TournamentService {
     async fn change_name(
         &self,
         request: tonic::Request<ChangeNameRequest>,
     ) -> std::result::Result<tonic::Response<ChangeNameResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
+        let change_name_request = request.into_inner();
+        let res = self
+            .tournament
+            .write(|tournament| {
+                tournament.inner.name = change_name_request.name;
+                Ok(())
+            })
+            .await;
 
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Ok(tonic::Response::new(ChangeNameResponse {
+        let response = match res {
+            None => ChangeNameResponse {
                 success: false,
                 error: "not loaded jet".to_string(),
-            }));
+            },
+            Some(Ok(_)) => ChangeNameResponse {
+                success: true,
+                error: "".to_string(),
+            },
+            Some(Err(err)) => ChangeNameResponse {
+                success: false,
+                error: format!("{:?}", err),
+            },
         };
 
-        let change_name_request = request.into_inner();
-        tournament.inner.name = change_name_request.name;
-
-        Ok(tonic::Response::new(ChangeNameResponse {
-            success: true,
-            error: "".to_string(),
-        }))
+        Ok(tonic::Response::new(response))
     }
 
     async fn load(
         &self,
         request: tonic::Request<LoadRequest>,
     ) -> std::result::Result<tonic::Response<LoadResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-
         let path_str = request.into_inner().path;
         let path = Path::new(&path_str);
         let res = Tournament::from_json_file(path);
 
         let response = match res {
             Ok(tourn) => {
-                *tourn_mut = Some(tourn);
+                self.tournament.replace(tourn).await;
                 LoadResponse {
                     success: true,
                     error: "".to_string(),
@@ -79,9 +94,7 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         request: tonic::Request<SaveRequest>,
     ) -> std::result::Result<tonic::Response<SaveResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-
-        let Some(ref mut tournament) = *tourn_mut else {
+        let Some(tournament) = self.tournament.snapshot() else {
             return Ok(tonic::Response::new(SaveResponse {
                 success: false,
                 error: "not loaded jet".to_string(),
@@ -110,21 +123,21 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         request: tonic::Request<AddDayRequest>,
     ) -> std::result::Result<tonic::Response<AddDayResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
-        };
-
         let Some(day) = request.into_inner().day else {
             return Err(tonic::Status::new(
                 tonic::Code::InvalidArgument,
                 "day is not set".to_string(),
             ));
         };
-        tournament.add_day(day);
+
+        let Some(res) = self
+            .tournament
+            .write(|tournament| tournament.add_day(day))
+            .await
+        else {
+            return Err(not_loaded());
+        };
+        res?;
 
         Ok(tonic::Response::new(AddDayResponse {}))
     }
@@ -133,16 +146,16 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         request: tonic::Request<RemoveDayRequest>,
     ) -> std::result::Result<tonic::Response<RemoveDayResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
+        let id = request.into_inner().id;
+        let Some(res) = self
+            .tournament
+            .write(|tournament| tournament.remove_day(id))
+            .await
+        else {
+            return Err(not_loaded());
         };
+        res?;
 
-        let id = request.into_inner().id;
-        tournament.remove_day(id);
         Ok(tonic::Response::new(RemoveDayResponse {}))
     }
 
@@ -150,12 +163,8 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         _request: tonic::Request<GetSimpleDaysRequest>,
     ) -> std::result::Result<tonic::Response<GetSimpleDaysResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
+        let Some(tournament) = self.tournament.snapshot() else {
+            return Err(not_loaded());
         };
 
         let days = tournament.get_simple_days();
@@ -167,12 +176,8 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         request: tonic::Request<GetDayDataRequest>,
     ) -> std::result::Result<tonic::Response<GetDayDataResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
+        let Some(tournament) = self.tournament.snapshot() else {
+            return Err(not_loaded());
         };
 
         let id = request.into_inner().id;
@@ -185,16 +190,18 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         request: tonic::Request<AddBewerbRequest>,
     ) -> std::result::Result<tonic::Response<AddBewerbResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
-        };
-
         let req = request.into_inner();
-        tournament.add_bewerb(req.name, req.n_rounds, req.n_groups);
+        let Some(res) = self
+            .tournament
+            .write(|tournament| {
+                tournament.add_bewerb(req.name, req.n_rounds, req.n_groups);
+                Ok(())
+            })
+            .await
+        else {
+            return Err(not_loaded());
+        };
+        res?;
 
         Ok(tonic::Response::new(AddBewerbResponse {}))
     }
@@ -203,16 +210,15 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         request: tonic::Request<RemoveBewerbRequest>,
     ) -> std::result::Result<tonic::Response<RemoveBewerbResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
-        };
-
         let req = request.into_inner();
-        tournament.remove_bewerb(req.bewerb_id);
+        let Some(res) = self
+            .tournament
+            .write(|tournament| tournament.remove_bewerb(req.bewerb_id))
+            .await
+        else {
+            return Err(not_loaded());
+        };
+        res?;
 
         Ok(tonic::Response::new(RemoveBewerbResponse {}))
     }
@@ -221,12 +227,8 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         _request: tonic::Request<GetSimpleBewerbsRequest>,
     ) -> std::result::Result<tonic::Response<GetSimpleBewerbsResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
+        let Some(tournament) = self.tournament.snapshot() else {
+            return Err(not_loaded());
         };
 
         let data = tournament
@@ -242,12 +244,8 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         _request: tonic::Request<GetAllFreeGroupsRequest>,
     ) -> std::result::Result<tonic::Response<GetAllFreeGroupsResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
+        let Some(tournament) = self.tournament.snapshot() else {
+            return Err(not_loaded());
         };
 
         let groups = tournament
@@ -263,14 +261,6 @@ impl tournament_server::Tournament for TournamentService {
         &self,
         request: tonic::Request<AddGroupToArenaRequest>,
     ) -> std::result::Result<tonic::Response<AddGroupToArenaResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
-        };
-
         let req = request.into_inner();
         let Some(group_id) = req.group_id else {
             return Err(tonic::Status::new(
@@ -286,23 +276,30 @@ impl tournament_server::Tournament for TournamentService {
             ));
         };
 
-        tournament.add_group_to_arena(&group_id.into(), &arena_id.into())?;
-
-        Ok(tonic::Response::new(AddGroupToArenaResponse {}))
+        let Some(res) = self
+            .tournament
+            .write(|tournament| tournament.add_group_to_arena(&group_id.into(), &arena_id.into()))
+            .await
+        else {
+            return Err(not_loaded());
+        };
+        // the response has no field for them, so the conflicts go along as
+        // binary metadata, one value per warning
+        let mut response = tonic::Response::new(AddGroupToArenaResponse {});
+        for warning in res? {
+            response.metadata_mut().append_bin(
+                SCHEDULE_WARNING_KEY,
+                MetadataValue::from_bytes(warning.to_string().as_bytes()),
+            );
+        }
+
+        Ok(response)
     }
 
     async fn free_up_group(
         &self,
         request: tonic::Request<FreeUpGroupRequest>,
     ) -> std::result::Result<tonic::Response<FreeUpGroupResponse>, tonic::Status> {
-        let mut tourn_mut = self.tournament.lock().await;
-        let Some(ref mut tournament) = *tourn_mut else {
-            return Err(tonic::Status::new(
-                tonic::Code::Internal,
-                "not loaded jet".to_string(),
-            ));
-        };
-
         let Some(group_id) = request.into_inner().group_id else {
             return Err(tonic::Status::new(
                 tonic::Code::InvalidArgument,
@@ -310,7 +307,14 @@ impl tournament_server::Tournament for TournamentService {
             ));
         };
 
-        tournament.freeup_group(&group_id.into())?;
+        let Some(res) = self
+            .tournament
+            .write(|tournament| tournament.freeup_group(&group_id.into()))
+            .await
+        else {
+            return Err(not_loaded());
+        };
+        res?;
 
         Ok(tonic::Response::new(FreeUpGroupResponse {}))
     }
@@ -0,0 +1,415 @@
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use std::fmt;
+
+use crate::arena_slot::ArenaSlotId;
+use crate::container::HasId;
+use crate::error::Error;
+use crate::fencer::AvailabilityWindow;
+use crate::group::GroupId;
+use crate::schedule::{Placement, Slot};
+use crate::timeslot::TimeslotId;
+use crate::tournament_core::Tournament;
+use crate::venue::PisteAttribute;
+
+pub fn default_min_rest_minutes() -> u32 {
+    30
+}
+
+pub fn default_round_gap_minutes() -> u32 {
+    15
+}
+
+/// A problem of the schedule that does not prevent it from being used.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScheduleWarning {
+    /// The fencer is in two groups that are fenced at the same time.
+    FencerDoubleBooked {
+        fencer: u32,
+        first: GroupId,
+        second: GroupId,
+    },
+    /// The fencer has less than the minimum rest between two groups.
+    FencerShortRest {
+        fencer: u32,
+        first: GroupId,
+        second: GroupId,
+        rest_minutes: i64,
+    },
+    /// The group starts before the round before it has ended plus the
+    /// minimum gap, `previous` is the group of that round ending last.
+    RoundOrder {
+        group: GroupId,
+        previous: GroupId,
+        gap_minutes: i64,
+    },
+    /// The group is fenced outside the availability windows of the fencer.
+    FencerUnavailable {
+        fencer: u32,
+        name: String,
+        group: GroupId,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        windows: Vec<AvailabilityWindow>,
+    },
+    /// The group is on a piste lacking an attribute its round requires.
+    MissingPisteAttribute {
+        group: GroupId,
+        piste: u32,
+        attribute: PisteAttribute,
+    },
+    /// Two days are on the same date.
+    DuplicateDate {
+        date: NaiveDate,
+        first: u32,
+        second: u32,
+    },
+    /// The day is before the first or after the last date of the
+    /// tournament.
+    DayOutsideTournament { day_id: u32, date: NaiveDate },
+    /// The timeslot overlaps a break of its day.
+    TimeslotInBreak {
+        timeslot: TimeslotId,
+        reason: String,
+    },
+    /// The timeslot starts before the day opens or ends after it closes.
+    TimeslotOutsideHours { timeslot: TimeslotId },
+}
+
+impl ScheduleWarning {
+    pub fn concerns(&self, group: &GroupId) -> bool {
+        match self {
+            Self::FencerDoubleBooked { first, second, .. }
+            | Self::FencerShortRest { first, second, .. } => first == group || second == group,
+            Self::RoundOrder {
+                group: later,
+                previous,
+                ..
+            } => later == group || previous == group,
+            Self::FencerUnavailable { group: own, .. }
+            | Self::MissingPisteAttribute { group: own, .. } => own == group,
+            Self::DuplicateDate { .. }
+            | Self::DayOutsideTournament { .. }
+            | Self::TimeslotInBreak { .. }
+            | Self::TimeslotOutsideHours { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for ScheduleWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FencerDoubleBooked {
+                fencer,
+                first,
+                second,
+            } => write!(
+                f,
+                "fencer {} is in {:?} and {:?} at the same time",
+                fencer, first, second
+            ),
+            Self::FencerShortRest {
+                fencer,
+                first,
+                second,
+                rest_minutes,
+            } => write!(
+                f,
+                "fencer {} has only {} minutes rest between {:?} and {:?}",
+                fencer, rest_minutes, first, second
+            ),
+            Self::RoundOrder {
+                group,
+                previous,
+                gap_minutes,
+            } => write!(
+                f,
+                "{:?} starts {} minutes after {:?} of the round before",
+                group, gap_minutes, previous
+            ),
+            Self::FencerUnavailable {
+                fencer,
+                name,
+                group,
+                start,
+                end,
+                windows,
+            } => {
+                let windows: Vec<String> = windows
+                    .iter()
+                    .map(|x| format!("{} - {}", x.start, x.end))
+                    .collect();
+                write!(
+                    f,
+                    "fencer {} ({}) is not available for {:?} from {} to {}, only {}",
+                    name,
+                    fencer,
+                    group,
+                    start,
+                    end,
+                    windows.join(", ")
+                )
+            }
+            Self::MissingPisteAttribute {
+                group,
+                piste,
+                attribute,
+            } => write!(
+                f,
+                "{:?} is on piste {} which has no {:?}",
+                group, piste, attribute
+            ),
+            Self::DuplicateDate {
+                date,
+                first,
+                second,
+            } => write!(f, "days {} and {} are both on {}", first, second, date),
+            Self::DayOutsideTournament { day_id, date } => {
+                write!(f, "day {} on {} is outside the tournament", day_id, date)
+            }
+            Self::TimeslotInBreak { timeslot, reason } => {
+                write!(f, "{:?} overlaps the break {:?}", timeslot, reason)
+            }
+            Self::TimeslotOutsideHours { timeslot } => {
+                write!(f, "{:?} is outside the opening hours", timeslot)
+            }
+        }
+    }
+}
+
+impl Tournament {
+    /// Cross-references the groups of every fencer with their arena slots
+    /// and reports overlaps and too short rest between consecutive groups.
+    pub fn find_fencer_conflicts(&self) -> Vec<ScheduleWarning> {
+        let placements = self.get_placements();
+        let min_rest = self.inner.min_rest_minutes as i64;
+        let mut res = Vec::new();
+
+        for fencer in self.fencers.iter() {
+            let fencer_id = fencer.get_id();
+            let mut own: Vec<&Placement> = placements
+                .iter()
+                .filter(|x| x.fencers.contains(&fencer_id))
+                .collect();
+            own.sort_by_key(|x| x.slot.start);
+
+            for (i, first) in own.iter().enumerate() {
+                for second in own.iter().skip(i + 1) {
+                    if first.slot.overlaps(&second.slot) {
+                        res.push(ScheduleWarning::FencerDoubleBooked {
+                            fencer: fencer_id,
+                            first: first.group.clone(),
+                            second: second.group.clone(),
+                        });
+                        continue;
+                    }
+
+                    let rest_minutes = (second.slot.start - first.slot.end).num_minutes();
+                    if rest_minutes < min_rest {
+                        res.push(ScheduleWarning::FencerShortRest {
+                            fencer: fencer_id,
+                            first: first.group.clone(),
+                            second: second.group.clone(),
+                            rest_minutes,
+                        });
+                    }
+                    break;
+                }
+            }
+        }
+
+        res
+    }
+
+    pub(crate) fn round_order_warnings(&self, placements: &[Placement]) -> Vec<ScheduleWarning> {
+        let gap = TimeDelta::minutes(self.inner.round_gap_minutes as i64);
+        let mut res = Vec::new();
+
+        for bewerb in self.bewerbs.iter() {
+            let mut previous: Option<&Placement> = None;
+            for round_id in bewerb.get_round_ids() {
+                let round: Vec<&Placement> = bewerb
+                    .get_groups_of_round(round_id)
+                    .iter()
+                    .filter_map(|id| placements.iter().find(|x| x.group == *id))
+                    .collect();
+
+                if let Some(previous) = previous {
+                    for placement in round.iter() {
+                        if placement.slot.start < previous.slot.end + gap {
+                            res.push(ScheduleWarning::RoundOrder {
+                                group: placement.group.clone(),
+                                previous: previous.group.clone(),
+                                gap_minutes: (placement.slot.start - previous.slot.end)
+                                    .num_minutes(),
+                            });
+                        }
+                    }
+                }
+
+                if let Some(last) = round.into_iter().max_by_key(|x| x.slot.end) {
+                    previous = Some(last);
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Reports groups that start before the previous round of their bewerb
+    /// has ended plus `round_gap_minutes`.
+    pub fn find_round_order_violations(&self) -> Vec<ScheduleWarning> {
+        self.round_order_warnings(&self.get_placements())
+    }
+
+    /// Fencers of the list that are not available for the whole slot.
+    pub fn get_unavailable_fencers(&self, fencers: &[u32], slot: &Slot) -> Vec<u32> {
+        fencers
+            .iter()
+            .filter(|x| {
+                self.fencers
+                    .get(**x)
+                    .is_some_and(|f| !f.is_available(slot.start, slot.end))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Reports fencers whose groups are outside their availability.
+    pub fn find_availability_conflicts(&self) -> Vec<ScheduleWarning> {
+        let mut res = Vec::new();
+        for placement in self.get_placements() {
+            for fencer in self.get_unavailable_fencers(&placement.fencers, &placement.slot) {
+                let Some(item) = self.fencers.get(fencer) else {
+                    continue;
+                };
+                res.push(ScheduleWarning::FencerUnavailable {
+                    fencer,
+                    name: item.name(),
+                    group: placement.group.clone(),
+                    start: placement.slot.start,
+                    end: placement.slot.end,
+                    windows: item.get_availability(),
+                });
+            }
+        }
+        res
+    }
+
+    /// Reports groups on pistes that lack an attribute their round
+    /// requires.
+    pub fn find_piste_attribute_violations(&self) -> Vec<ScheduleWarning> {
+        let mut res = Vec::new();
+        for placement in self.get_placements() {
+            let Some(bewerb) = self.bewerbs.get(placement.group.bewerb_id) else {
+                continue;
+            };
+
+            let piste = placement.slot.piste;
+            for attribute in bewerb.get_required_attributes(placement.group.round_id) {
+                if !self.piste_has_attributes(piste, &[attribute]) {
+                    res.push(ScheduleWarning::MissingPisteAttribute {
+                        group: placement.group.clone(),
+                        piste,
+                        attribute,
+                    });
+                }
+            }
+        }
+        res
+    }
+
+    /// Reports days sharing a date or outside the tournament and
+    /// timeslots outside the opening hours or in a break of their day.
+    pub fn find_day_problems(&self) -> Vec<ScheduleWarning> {
+        let first_date = self.inner.first_date;
+        let last_date = self.inner.last_date;
+        let days: Vec<_> = self.days.iter().collect();
+        let mut res = Vec::new();
+
+        for (i, day) in days.iter().enumerate() {
+            let date = day.date();
+            if let Some(other) = days.iter().skip(i + 1).find(|x| x.date() == date) {
+                res.push(ScheduleWarning::DuplicateDate {
+                    date,
+                    first: day.get_id(),
+                    second: other.get_id(),
+                });
+            }
+            if first_date.is_some_and(|x| date < x) || last_date.is_some_and(|x| date > x) {
+                res.push(ScheduleWarning::DayOutsideTournament {
+                    day_id: day.get_id(),
+                    date,
+                });
+            }
+
+            for (timeslot_id, brk) in day.get_timeslots_outside_hours() {
+                let timeslot = TimeslotId {
+                    day_id: day.get_id(),
+                    timeslot_id,
+                };
+                res.push(match brk {
+                    Some(brk) => ScheduleWarning::TimeslotInBreak {
+                        timeslot,
+                        reason: brk.reason,
+                    },
+                    None => ScheduleWarning::TimeslotOutsideHours { timeslot },
+                });
+            }
+        }
+
+        res
+    }
+
+    /// Checks the whole schedule.
+    pub fn validate_schedule(&self) -> Vec<ScheduleWarning> {
+        let mut res = self.find_fencer_conflicts();
+        res.append(&mut self.find_round_order_violations());
+        res.append(&mut self.find_availability_conflicts());
+        res.append(&mut self.find_piste_attribute_violations());
+        res.append(&mut self.find_day_problems());
+        res
+    }
+
+    /// All warnings that concern the group.
+    pub fn get_group_warnings(&self, group: &GroupId) -> Vec<ScheduleWarning> {
+        self.validate_schedule()
+            .into_iter()
+            .filter(|x| x.concerns(group))
+            .collect()
+    }
+
+    /// Fails if `enforce_round_order` is set and putting the group into the
+    /// arena slot would break the order of the rounds.
+    pub(crate) fn check_round_order(
+        &self,
+        group: &GroupId,
+        arena: &ArenaSlotId,
+    ) -> Result<(), Error> {
+        if !self.inner.enforce_round_order {
+            return Ok(());
+        }
+
+        let Some(run) = self.get_slot_run(arena, self.get_group_minutes(group)) else {
+            return Ok(());
+        };
+        let Some(placement) = Placement::new(group.clone(), &run, Vec::new()) else {
+            return Ok(());
+        };
+
+        let mut placements: Vec<Placement> = self
+            .get_placements()
+            .into_iter()
+            .filter(|x| x.group != *group && !x.arenas.iter().any(|a| placement.arenas.contains(a)))
+            .collect();
+        placements.push(placement);
+
+        let violation = self
+            .round_order_warnings(&placements)
+            .into_iter()
+            .find(|x| x.concerns(group));
+        match violation {
+            Some(violation) => Err(Error::InvalidInput(violation.to_string())),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::arena_slot::ArenaSlotId;
+use crate::error::Error;
+use crate::group::GroupId;
+use crate::timeslot::TimeslotId;
+use crate::tournament_core::Tournament;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum PisteAttribute {
+    ScoringApparatus,
+    Video,
+    Streaming,
+}
+
+/// A piste of the venue. `id` is the piste number the arena slots refer to.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Piste {
+    pub id: u32,
+    pub name: String,
+    pub attributes: Vec<PisteAttribute>,
+}
+
+impl Piste {
+    pub fn has_attributes(&self, attributes: &[PisteAttribute]) -> bool {
+        attributes.iter().all(|x| self.attributes.contains(x))
+    }
+}
+
+impl Tournament {
+    pub fn get_pistes(&self) -> &[Piste] {
+        &self.inner.pistes
+    }
+
+    pub fn get_piste(&self, id: u32) -> Option<&Piste> {
+        self.inner.pistes.iter().find(|x| x.id == id)
+    }
+
+    /// Names the piste and sets its attributes, replacing an earlier
+    /// definition of the same piste.
+    pub fn set_piste(&mut self, piste: Piste) {
+        match self.inner.pistes.iter_mut().find(|x| x.id == piste.id) {
+            Some(old) => *old = piste,
+            None => {
+                self.inner.pistes.push(piste);
+                self.inner.pistes.sort_by_key(|x| x.id);
+            }
+        }
+    }
+
+    pub fn remove_piste(&mut self, id: u32) -> Result<(), Error> {
+        let Some(pos) = self.inner.pistes.iter().position(|x| x.id == id) else {
+            return Err(Error::InvalidInput(format!("Ivalid piste {:?}", id)));
+        };
+        self.inner.pistes.remove(pos);
+        Ok(())
+    }
+
+    /// Name of the piste, pistes without a definition are numbered from 1.
+    pub fn piste_name(&self, id: u32) -> String {
+        match self.get_piste(id) {
+            Some(piste) => piste.name.clone(),
+            None => format!("Piste {}", id + 1),
+        }
+    }
+
+    /// Pistes without a definition have no attributes.
+    pub fn piste_has_attributes(&self, id: u32, attributes: &[PisteAttribute]) -> bool {
+        attributes.is_empty()
+            || self
+                .get_piste(id)
+                .is_some_and(|x| x.has_attributes(attributes))
+    }
+
+    /// Frees the groups and elimination bouts using a piste of the day
+    /// that is not available anymore and returns the groups.
+    fn freeup_disabled_pistes(&mut self, day_id: u32) -> Result<Vec<GroupId>, Error> {
+        let Some(day) = self.days.get(day_id) else {
+            return Err(Error::InvalidInput(format!("Ivalid day_id {:?}", day_id)));
+        };
+
+        let mut displaced = Vec::new();
+        let disabled = day
+            .get_all_arenas()
+            .into_iter()
+            .filter(|x| !day.is_piste_enabled(x.id().timeslot_id, x.piste));
+        for arena in disabled {
+            if let Some(bout) = arena.get_bout() {
+                Self::freeup_bout(&bout);
+            }
+            if let Some(group) = arena.get_group() {
+                if !displaced.contains(&group.id()) {
+                    displaced.push(group.id());
+                }
+            }
+        }
+        for group_id in displaced.iter() {
+            self.freeup_group(group_id)?;
+        }
+        Ok(displaced)
+    }
+
+    /// Limits the day to `pistes`, all pistes are available if empty.
+    /// Groups on pistes no longer available are freed and returned,
+    /// elimination bouts there are freed to be dispatched again.
+    pub fn set_day_pistes(&mut self, day_id: u32, pistes: Vec<u32>) -> Result<Vec<GroupId>, Error> {
+        let Some(day) = self.days.get_mut(day_id) else {
+            return Err(Error::InvalidInput(format!("Ivalid day_id {:?}", day_id)));
+        };
+        day.enabled_pistes = pistes;
+
+        self.freeup_disabled_pistes(day_id)
+    }
+
+    /// Limits the timeslot to `pistes`, the pistes of the day are available
+    /// if empty. Groups and bouts are freed as with `set_day_pistes`.
+    pub fn set_timeslot_pistes(
+        &mut self,
+        id: &TimeslotId,
+        pistes: Vec<u32>,
+    ) -> Result<Vec<GroupId>, Error> {
+        let ts = self
+            .days
+            .get_mut(id.day_id)
+            .and_then(|x| x.get_timeslot_mut(id.timeslot_id));
+        let Some(ts) = ts else {
+            return Err(Error::InvalidInput(format!("Ivalid timeslot_id {:?}", id)));
+        };
+        ts.enabled_pistes = pistes;
+
+        self.freeup_disabled_pistes(id.day_id)
+    }
+
+    /// Whether the piste of the arena slot is available in its timeslot.
+    pub fn is_arena_enabled(&self, id: &ArenaSlotId) -> bool {
+        let (Some(day), Some(arena)) = (self.days.get(id.day_id), self.get_arena(id)) else {
+            return false;
+        };
+        day.is_piste_enabled(id.timeslot_id, arena.piste)
+    }
+}